@@ -0,0 +1,530 @@
+use crate::canonical::canonical_hash;
+use crate::logic::Technique;
+use crate::matrix::SudokuMatrix;
+use crate::rating::{Difficulty, RatedPuzzle, Rating};
+use crate::solver::count_solutions;
+use rand::Rng;
+use rand::seq::IteratorRandom;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "# sudoku puzzle bank v1";
+const COLUMNS: &str = "# puzzle\tsolution\tdifficulty\thardest_technique\tsteps\tclues\tcanonical_hash\tdrawn\tsource";
+
+/**
+ * A puzzle stored in a `PuzzleBank`.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BankEntry {
+    pub puzzle: SudokuMatrix,
+    pub solution: SudokuMatrix,
+    pub rating: Rating,
+    /// Hash of the canonical form, shared by all equivalent puzzles.
+    pub canonical_hash: u64,
+    /// Free text describing where the puzzle comes from, such as a generator or a file.
+    pub source: String,
+    /// Whether the puzzle has already been served by `PuzzleBank::draw`.
+    pub drawn: bool,
+}
+
+impl BankEntry {
+    /**
+     * Create an entry from a rated puzzle, computing its canonical hash.
+     */
+    pub fn new(rated: RatedPuzzle, source: &str) -> Self {
+        BankEntry {
+            canonical_hash: canonical_hash(&rated.puzzle),
+            puzzle: rated.puzzle,
+            solution: rated.solution,
+            rating: rated.rating,
+            source: source.to_string(),
+            drawn: false,
+        }
+    }
+
+    pub fn clue_count(&self) -> usize {
+        self.rating.clue_count
+    }
+
+    fn to_line(&self) -> String {
+        let technique = self
+            .rating
+            .hardest_technique
+            .map_or("-".to_string(), |t| t.to_string());
+        // Tabs and line breaks would break the format, so they cannot be kept in the source.
+        let source: String = self
+            .source
+            .chars()
+            .map(|ch| if ch.is_control() { ' ' } else { ch })
+            .collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{:016x}\t{}\t{}",
+            self.puzzle.to_line(),
+            self.solution.to_line(),
+            self.rating.difficulty,
+            technique,
+            self.rating.steps,
+            self.rating.clue_count,
+            self.canonical_hash,
+            if self.drawn { 1 } else { 0 },
+            source
+        )
+    }
+
+    fn from_line(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [
+            puzzle,
+            solution,
+            difficulty,
+            technique,
+            steps,
+            clues,
+            hash,
+            drawn,
+            source,
+        ] = fields[..]
+        else {
+            return Err(format!("expected 9 fields, found {}", fields.len()));
+        };
+        let puzzle: SudokuMatrix = puzzle.parse()?;
+        let solution: SudokuMatrix = solution.parse()?;
+        if !solution.is_complete() || !solution.is_compatible() {
+            return Err("the solution is not a valid solved grid".to_string());
+        }
+        let clue_count = clues
+            .parse()
+            .map_err(|_| format!("invalid clue count `{}`", clues))?;
+        if clue_count != puzzle.filled_count() {
+            return Err(format!(
+                "clue count {} does not match the puzzle with {} clues",
+                clue_count,
+                puzzle.filled_count()
+            ));
+        }
+        let canonical_hash = u64::from_str_radix(hash, 16)
+            .map_err(|_| format!("invalid canonical hash `{}`", hash))?;
+        let hardest_technique = match technique {
+            "-" => None,
+            _ => Some(technique.parse::<Technique>()?),
+        };
+        Ok(BankEntry {
+            puzzle,
+            solution,
+            rating: Rating {
+                difficulty: difficulty.parse()?,
+                hardest_technique,
+                steps: steps
+                    .parse()
+                    .map_err(|_| format!("invalid step count `{}`", steps))?,
+                clue_count,
+            },
+            canonical_hash,
+            drawn: match drawn {
+                "0" => false,
+                "1" => true,
+                _ => return Err(format!("invalid drawn flag `{}`", drawn)),
+            },
+            source: source.to_string(),
+        })
+    }
+
+    /**
+     * Check that the stored solution is the unique solution of the puzzle, and that the
+     * stored canonical hash is the one of the puzzle.
+     *
+     * These checks are slow, so they are not run when a bank is opened.
+     */
+    pub fn verify(&self) -> Result<(), String> {
+        let mismatch = (0..81).any(|idx| {
+            let v = self.puzzle.get_value(idx / 9, idx % 9);
+            v != 0 && v != self.solution.get_value(idx / 9, idx % 9)
+        });
+        if mismatch || count_solutions(&self.puzzle, 2) != 1 {
+            return Err("the solution is not the unique solution of the puzzle".to_string());
+        }
+        if self.canonical_hash != canonical_hash(&self.puzzle) {
+            return Err(format!(
+                "canonical hash `{:016x}` does not match the puzzle",
+                self.canonical_hash
+            ));
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Criteria to select puzzles from a `PuzzleBank`. Unset criteria match every puzzle.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BankQuery {
+    pub difficulty: Option<Difficulty>,
+    pub min_clues: Option<usize>,
+    pub max_clues: Option<usize>,
+}
+
+impl BankQuery {
+    /**
+     * Determine whether an entry meets the criteria.
+     */
+    pub fn matches(&self, entry: &BankEntry) -> bool {
+        self.difficulty
+            .is_none_or(|difficulty| entry.rating.difficulty == difficulty)
+            && self.min_clues.is_none_or(|min| entry.clue_count() >= min)
+            && self.max_clues.is_none_or(|max| entry.clue_count() <= max)
+    }
+}
+
+/**
+ * Errors raised when loading a `PuzzleBank`.
+ */
+#[derive(Debug)]
+pub enum BankError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// A line of the file is malformed. Line numbers start from 1.
+    Parse { line: usize, message: String },
+    /// An entry fails `BankEntry::verify`. Entries are numbered from 1, in file order.
+    Invalid { entry: usize, message: String },
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::Io(err) => write!(f, "cannot read the puzzle bank: {}", err),
+            BankError::Parse { line, message } => {
+                write!(f, "invalid puzzle bank entry on line {}: {}", line, message)
+            }
+            BankError::Invalid { entry, message } => {
+                write!(f, "puzzle bank entry {} is wrong: {}", entry, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BankError {}
+
+impl From<io::Error> for BankError {
+    fn from(err: io::Error) -> Self {
+        BankError::Io(err)
+    }
+}
+
+/**
+ * A collection of rated puzzles backed by a local text file, so that puzzles can be
+ * served instantly instead of being generated on demand.
+ *
+ * The file starts with a header comment and holds one puzzle per line, with tab
+ * separated fields. Equivalent puzzles are only stored once.
+ */
+#[derive(Debug)]
+pub struct PuzzleBank {
+    path: PathBuf,
+    entries: Vec<BankEntry>,
+    hashes: HashSet<u64>,
+    by_difficulty: HashMap<Difficulty, Vec<usize>>,
+}
+
+impl PuzzleBank {
+    /**
+     * Open the bank stored at `path`. A missing file gives an empty bank, which is
+     * created on the first `save`.
+     *
+     * Only the format of the entries is checked; use `verify` to check their content.
+     */
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BankError> {
+        let mut bank = PuzzleBank {
+            path: path.as_ref().to_path_buf(),
+            entries: vec![],
+            hashes: HashSet::new(),
+            by_difficulty: HashMap::new(),
+        };
+        let content = match fs::read_to_string(&bank.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(bank),
+            Err(err) => return Err(err.into()),
+        };
+        for (idx, line) in content.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = BankEntry::from_line(line).map_err(|message| BankError::Parse {
+                line: idx + 1,
+                message,
+            })?;
+            bank.insert(entry);
+        }
+        Ok(bank)
+    }
+
+    /**
+     * Check every entry with `BankEntry::verify`.
+     *
+     * This takes a fraction of a second per entry, so it is meant for occasional checks
+     * of a bank file rather than for every `open`.
+     */
+    pub fn verify(&self) -> Result<(), BankError> {
+        for (idx, entry) in self.entries.iter().enumerate() {
+            entry.verify().map_err(|message| BankError::Invalid {
+                entry: idx + 1,
+                message,
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /**
+     * Write the bank back to its file.
+     *
+     * The content is written to a temporary file first, so that an interrupted save
+     * does not lose the bank.
+     */
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = format!("{}\n{}\n", HEADER, COLUMNS);
+        for entry in self.entries.iter() {
+            content.push_str(&entry.to_line());
+            content.push('\n');
+        }
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[BankEntry] {
+        &self.entries
+    }
+
+    /**
+     * Determine whether the bank holds a puzzle equivalent to `mat`.
+     */
+    pub fn contains(&self, mat: &SudokuMatrix) -> bool {
+        self.hashes.contains(&canonical_hash(mat))
+    }
+
+    /**
+     * Add an entry to the bank. Return `false` and drop the entry if an equivalent
+     * puzzle is already stored.
+     */
+    pub fn insert(&mut self, entry: BankEntry) -> bool {
+        if !self.hashes.insert(entry.canonical_hash) {
+            return false;
+        }
+        self.by_difficulty
+            .entry(entry.rating.difficulty)
+            .or_default()
+            .push(self.entries.len());
+        self.entries.push(entry);
+        true
+    }
+
+    fn matching_indices<'a>(&'a self, query: &'a BankQuery) -> impl Iterator<Item = usize> + 'a {
+        let indices: Box<dyn Iterator<Item = usize>> = match query.difficulty {
+            Some(difficulty) => Box::new(
+                self.by_difficulty
+                    .get(&difficulty)
+                    .into_iter()
+                    .flatten()
+                    .copied(),
+            ),
+            None => Box::new(0..self.entries.len()),
+        };
+        indices.filter(|idx| query.matches(&self.entries[*idx]))
+    }
+
+    /**
+     * Return all entries meeting the criteria of the query, in insertion order.
+     */
+    pub fn query<'a>(&'a self, query: &'a BankQuery) -> impl Iterator<Item = &'a BankEntry> + 'a {
+        self.matching_indices(query).map(|idx| &self.entries[idx])
+    }
+
+    /**
+     * Draw a random entry meeting the criteria that has not been drawn before, and mark
+     * it as drawn. Save the bank to remember the draws across runs.
+     *
+     * Return `None` if every matching entry has already been drawn.
+     */
+    pub fn draw<R: Rng + ?Sized>(&mut self, query: &BankQuery, rng: &mut R) -> Option<&BankEntry> {
+        let idx = self
+            .matching_indices(query)
+            .filter(|idx| !self.entries[*idx].drawn)
+            .choose(rng)?;
+        self.entries[idx].drawn = true;
+        Some(&self.entries[idx])
+    }
+
+    /**
+     * Make every entry available to `draw` again.
+     */
+    pub fn reset_draws(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.drawn = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rating::rate_puzzle;
+    use crate::solver::find_solution;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /**
+     * Return a path in the temporary directory that no other test or test run uses.
+     */
+    fn temp_path(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sudoku-bank-{}-{}.txt", std::process::id(), test))
+    }
+
+    fn entries() -> Vec<BankEntry> {
+        // A fixed solution, so every test builds the same entries.
+        let solution = find_solution(&SudokuMatrix::new()).unwrap();
+        [5, 6, 7]
+            .into_iter()
+            .map(|step| {
+                let mut puzzle = solution.clone();
+                for i in (0..81).step_by(step) {
+                    puzzle.set_value(i / 9, i % 9, 0);
+                }
+                let rating = rate_puzzle(&puzzle).unwrap();
+                let rated = RatedPuzzle {
+                    puzzle,
+                    solution: solution.clone(),
+                    rating,
+                };
+                BankEntry::new(rated, &format!("test\tstep {}", step))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bank_round_trip() {
+        let path = temp_path("round-trip");
+        let mut bank = PuzzleBank::open(&path).unwrap();
+        assert!(bank.is_empty());
+        for entry in entries() {
+            assert!(bank.insert(entry.clone()));
+            assert!(!bank.insert(entry));
+        }
+        assert_eq!(bank.len(), 3);
+        assert!(bank.contains(&bank.entries()[0].puzzle.transpose()));
+        bank.save().unwrap();
+
+        let loaded = PuzzleBank::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entries().len(), 3);
+        assert_eq!(loaded.entries()[1].source, "test step 6");
+        assert_eq!(loaded.entries()[2].puzzle, bank.entries()[2].puzzle);
+        assert_eq!(loaded.entries()[2].rating, bank.entries()[2].rating);
+    }
+
+    #[test]
+    fn test_bank_query_and_draw() {
+        let path = temp_path("query-and-draw");
+        let mut bank = PuzzleBank::open(&path).unwrap();
+        for entry in entries() {
+            bank.insert(entry);
+        }
+        let query = BankQuery {
+            difficulty: Some(Difficulty::Easy),
+            min_clues: Some(66),
+            max_clues: None,
+        };
+        let expected: Vec<usize> = bank
+            .entries()
+            .iter()
+            .filter(|e| e.rating.difficulty == Difficulty::Easy && e.clue_count() >= 66)
+            .map(|e| e.clue_count())
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(
+            bank.query(&query)
+                .map(|e| e.clue_count())
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut drawn = vec![];
+        while let Some(entry) = bank.draw(&query, &mut rng) {
+            drawn.push(entry.clue_count());
+        }
+        drawn.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(drawn, expected);
+        bank.reset_draws();
+        assert!(bank.draw(&query, &mut rng).is_some());
+    }
+
+    #[test]
+    fn test_bank_parse_error() {
+        let path = temp_path("parse-error");
+        fs::write(&path, format!("{}\nnot a puzzle\n", HEADER)).unwrap();
+        let result = PuzzleBank::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(BankError::Parse { line: 2, .. })));
+    }
+
+    #[test]
+    fn test_bank_verify() {
+        let entry = entries().remove(0);
+        assert_eq!(entry.verify(), Ok(()));
+
+        // A wrong hash is only found by `verify`, so that opening a bank stays fast.
+        let mut bad_hash = entry.clone();
+        bad_hash.canonical_hash ^= 1;
+        let loaded = BankEntry::from_line(&bad_hash.to_line()).unwrap();
+        assert_eq!(loaded.canonical_hash, bad_hash.canonical_hash);
+        assert!(loaded.verify().unwrap_err().contains("canonical hash"));
+
+        // A valid grid, but not the solution of the puzzle.
+        let mut bad_solution = entry.clone();
+        bad_solution.solution = entry.solution.transpose();
+        assert!(
+            bad_solution
+                .verify()
+                .unwrap_err()
+                .contains("unique solution")
+        );
+
+        // A puzzle whose stored solution is one of several.
+        let mut loose = entry.clone();
+        for r in 0..9 {
+            loose.puzzle.set_value(r, 0, 0);
+            loose.puzzle.set_value(r, 1, 0);
+        }
+        loose.canonical_hash = canonical_hash(&loose.puzzle);
+        loose.rating.clue_count = loose.puzzle.filled_count();
+        assert!(loose.verify().unwrap_err().contains("unique solution"));
+
+        let mut bank = PuzzleBank::open(temp_path("verify")).unwrap();
+        bank.insert(entry);
+        bank.insert(bad_hash);
+        assert!(matches!(
+            bank.verify(),
+            Err(BankError::Invalid { entry: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::format::parse_puzzles;
+use crate::logic::LogicSolver;
+use crate::matrix::SudokuMatrix;
+use crate::solver::{count_solutions_counting, find_solution_counting, solve_sudoku_counting};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/**
+ * Solver backends that can be benchmarked.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SolverBackend {
+    /// `solve_sudoku`: candidate sets with propagation and backtracking.
+    Backtracking,
+    /// `find_solution`: bitmask search stopping at the first solution.
+    Bitmask,
+    /// `count_solutions` with a limit of 2: bitmask search proving uniqueness.
+    BitmaskCount,
+    /// `LogicSolver`: human techniques only, which do not solve every puzzle.
+    Logic,
+}
+
+impl SolverBackend {
+    pub const ALL: [SolverBackend; 4] = [
+        SolverBackend::Backtracking,
+        SolverBackend::Bitmask,
+        SolverBackend::BitmaskCount,
+        SolverBackend::Logic,
+    ];
+
+    /**
+     * Run the backend on a puzzle.
+     *
+     * # Returns
+     * Whether the puzzle was solved (for `BitmaskCount`, proven unique), and the number
+     * of search nodes visited. Nodes are logic steps for `Logic`.
+     */
+    pub fn run(&self, mat: &SudokuMatrix) -> (bool, usize) {
+        match self {
+            SolverBackend::Backtracking => {
+                let mut solved = mat.clone();
+                let mut nodes = 0;
+                let is_solved = solve_sudoku_counting(&mut solved, false, &mut nodes);
+                (is_solved, nodes)
+            }
+            SolverBackend::Bitmask => {
+                let (solution, nodes) = find_solution_counting(mat);
+                (solution.is_some(), nodes)
+            }
+            SolverBackend::BitmaskCount => {
+                let (solutions, nodes) = count_solutions_counting(mat, 2);
+                (solutions == 1, nodes)
+            }
+            SolverBackend::Logic => {
+                let mut solver = LogicSolver::new(mat);
+                let steps = solver.solve();
+                (solver.is_solved(), steps.len())
+            }
+        }
+    }
+}
+
+impl fmt::Display for SolverBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SolverBackend::Backtracking => "backtracking",
+            SolverBackend::Bitmask => "bitmask",
+            SolverBackend::BitmaskCount => "bitmask-count",
+            SolverBackend::Logic => "logic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SolverBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SolverBackend::ALL
+            .into_iter()
+            .find(|backend| backend.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown backend `{}`, expected one of: backtracking, bitmask, bitmask-count, logic",
+                    s
+                )
+            })
+    }
+}
+
+/**
+ * A named set of puzzles.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PuzzleSet {
+    pub name: String,
+    pub puzzles: Vec<SudokuMatrix>,
+}
+
+const BUNDLED_SETS: [(&str, &str); 4] = [
+    ("easy", include_str!("../data/bench/easy.txt")),
+    ("hard", include_str!("../data/bench/hard.txt")),
+    ("17-clue", include_str!("../data/bench/17-clue.txt")),
+    (
+        "pathological",
+        include_str!("../data/bench/pathological.txt"),
+    ),
+];
+
+/**
+ * Return the puzzle sets bundled with the crate: generated easy puzzles, well-known and
+ * generated hard puzzles, 17-clue puzzles, and puzzles built against backtracking that
+ * tries digits in increasing order.
+ */
+pub fn bundled_puzzle_sets() -> Vec<PuzzleSet> {
+    BUNDLED_SETS
+        .iter()
+        .map(|(name, content)| PuzzleSet {
+            name: name.to_string(),
+            puzzles: parse_puzzles(content, None).expect("bundled puzzle sets are valid"),
+        })
+        .collect()
+}
+
+/**
+ * Measurements of a backend over a puzzle set.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BenchResult {
+    pub backend: SolverBackend,
+    pub set: String,
+    /// The number of puzzles run, counting every repetition.
+    pub runs: usize,
+    /// The number of runs that solved their puzzle.
+    pub solved: usize,
+    pub total_time: Duration,
+    pub median_latency: Duration,
+    pub p99_latency: Duration,
+    /// The number of search nodes visited over all runs.
+    pub nodes: usize,
+}
+
+impl BenchResult {
+    pub fn puzzles_per_second(&self) -> f64 {
+        self.runs as f64 / self.total_time.as_secs_f64().max(1e-9)
+    }
+
+    pub fn nodes_per_puzzle(&self) -> f64 {
+        self.nodes as f64 / self.runs.max(1) as f64
+    }
+}
+
+/**
+ * Return the latency below which a fraction `p` of the sorted latencies fall, by the
+ * nearest-rank method.
+ */
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/**
+ * Run a backend over every puzzle of a set, `repeat` times, timing each puzzle.
+ */
+pub fn run_benchmark(backend: SolverBackend, set: &PuzzleSet, repeat: usize) -> BenchResult {
+    let mut latencies = vec![];
+    let mut solved = 0;
+    let mut nodes = 0;
+    let start = Instant::now();
+    for _ in 0..repeat {
+        for puzzle in set.puzzles.iter() {
+            let puzzle_start = Instant::now();
+            let (is_solved, puzzle_nodes) = backend.run(puzzle);
+            latencies.push(puzzle_start.elapsed());
+            solved += is_solved as usize;
+            nodes += puzzle_nodes;
+        }
+    }
+    let total_time = start.elapsed();
+    latencies.sort();
+    BenchResult {
+        backend,
+        set: set.name.clone(),
+        runs: latencies.len(),
+        solved,
+        total_time,
+        median_latency: percentile(&latencies, 0.5),
+        p99_latency: percentile(&latencies, 0.99),
+        nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::count_solutions;
+
+    #[test]
+    fn test_bundled_puzzle_sets() {
+        let sets = bundled_puzzle_sets();
+        assert_eq!(sets.len(), 4);
+        for set in sets.iter() {
+            assert!(!set.puzzles.is_empty());
+            for puzzle in set.puzzles.iter() {
+                assert_eq!(count_solutions(puzzle, 2), 1, "{}", set.name);
+            }
+        }
+        let clue_17 = sets.iter().find(|set| set.name == "17-clue").unwrap();
+        assert!(clue_17.puzzles.iter().all(|p| p.filled_count() == 17));
+    }
+
+    #[test]
+    fn test_run_benchmark() {
+        let sets = bundled_puzzle_sets();
+        let result = run_benchmark(SolverBackend::Bitmask, &sets[2], 2);
+        assert_eq!(result.runs, 2 * sets[2].puzzles.len());
+        assert_eq!(result.solved, result.runs);
+        assert!(result.nodes >= result.runs);
+        assert!(result.median_latency <= result.p99_latency);
+        for backend in SolverBackend::ALL {
+            assert_eq!(backend.to_string().parse::<SolverBackend>(), Ok(backend));
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 0.5), Duration::from_millis(50));
+        assert_eq!(percentile(&latencies, 0.99), Duration::from_millis(99));
+        assert_eq!(percentile(&[], 0.5), Duration::ZERO);
+    }
+}
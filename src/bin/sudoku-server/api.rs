@@ -0,0 +1,311 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde_json::{Map, Value, json};
+use std::time::Instant;
+use sudoku_lib::{
+    DeadlineExceeded, Difficulty, Hint, LogicStep, SudokuMatrix, Symmetry, count_solutions_before,
+    create_rated_symmetric_matrix_before, find_hint_before, find_solution_before, parse_puzzles,
+    rate_puzzle_before,
+};
+
+/**
+ * Largest solution limit accepted by `/count`, so that a request cannot enumerate the
+ * solutions of an empty grid.
+ */
+const MAX_COUNT_LIMIT: usize = 10000;
+
+/**
+ * A JSON response with its HTTP status code.
+ */
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response { status: 200, body }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Response {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+type ApiResult = Result<Response, Response>;
+
+impl From<DeadlineExceeded> for Response {
+    fn from(_: DeadlineExceeded) -> Self {
+        Response::error(504, "the request timed out")
+    }
+}
+
+/**
+ * Handle a request of the API.
+ *
+ * Every endpoint but `GET /health` takes a `POST` with a JSON object body. Puzzles are
+ * strings in the line or grid format, as read by `sudoku_lib::parse_puzzles`. Searches
+ * stop at `deadline`, and the request is then answered with a 504 response.
+ */
+pub fn handle(method: &str, path: &str, body: &[u8], deadline: Instant) -> Response {
+    let result = match (method, path) {
+        ("GET", "/health") => Ok(Response::ok(json!({ "status": "ok" }))),
+        ("POST", "/generate") => parse_body(body).and_then(|params| generate(&params, deadline)),
+        ("POST", "/solve") => parse_body(body).and_then(|params| solve(&params, deadline)),
+        ("POST", "/count") => parse_body(body).and_then(|params| count(&params, deadline)),
+        ("POST", "/rate") => parse_body(body).and_then(|params| rate(&params, deadline)),
+        ("POST", "/hint") => parse_body(body).and_then(|params| hint(&params, deadline)),
+        ("POST", "/validate") => parse_body(body).and_then(|params| validate(&params, deadline)),
+        (_, "/health" | "/generate" | "/solve" | "/count" | "/rate" | "/hint" | "/validate") => {
+            Err(Response::error(405, "method not allowed"))
+        }
+        _ => Err(Response::error(404, "not found")),
+    };
+    result.unwrap_or_else(|err| err)
+}
+
+fn parse_body(body: &[u8]) -> Result<Map<String, Value>, Response> {
+    if body.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(Map::new());
+    }
+    match serde_json::from_slice(body) {
+        Ok(Value::Object(params)) => Ok(params),
+        Ok(_) => Err(Response::error(400, "the body must be a JSON object")),
+        Err(err) => Err(Response::error(400, &format!("invalid JSON: {}", err))),
+    }
+}
+
+/**
+ * Read an optional string parameter and parse it with `FromStr`.
+ */
+fn parse_param<T: std::str::FromStr<Err = String>>(
+    params: &Map<String, Value>,
+    key: &str,
+) -> Result<Option<T>, Response> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => s
+            .parse()
+            .map(Some)
+            .map_err(|e: String| Response::error(400, &e)),
+        Some(_) => Err(Response::error(400, &format!("`{}` must be a string", key))),
+    }
+}
+
+fn parse_u64_param(params: &Map<String, Value>, key: &str) -> Result<Option<u64>, Response> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            Response::error(400, &format!("`{}` must be a non-negative integer", key))
+        }),
+    }
+}
+
+fn parse_puzzle(params: &Map<String, Value>) -> Result<SudokuMatrix, Response> {
+    let Some(Value::String(text)) = params.get("puzzle") else {
+        return Err(Response::error(400, "`puzzle` must be a string"));
+    };
+    let mut puzzles = parse_puzzles(text, None)
+        .map_err(|e| Response::error(400, &format!("invalid puzzle: {}", e)))?;
+    if puzzles.len() != 1 {
+        return Err(Response::error(
+            400,
+            "`puzzle` must hold exactly one puzzle",
+        ));
+    }
+    Ok(puzzles.remove(0))
+}
+
+fn step_json(step: &LogicStep) -> Value {
+    let cells = |cells: &[(usize, usize, u8)]| -> Vec<Value> {
+        cells
+            .iter()
+            .map(|(r, c, v)| json!({ "row": r, "col": c, "value": v }))
+            .collect()
+    };
+    json!({
+        "technique": step.technique.to_string(),
+        "explanation": step.explanation,
+        "placements": cells(&step.placements),
+        "eliminations": cells(&step.eliminations),
+    })
+}
+
+fn generate(params: &Map<String, Value>, deadline: Instant) -> ApiResult {
+    let difficulty = parse_param(params, "difficulty")?.unwrap_or(Difficulty::Easy);
+    let symmetry = parse_param(params, "symmetry")?.unwrap_or(Symmetry::None);
+    let mut rng = match parse_u64_param(params, "seed")? {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let rated = create_rated_symmetric_matrix_before(difficulty, symmetry, &mut rng, deadline)?;
+    Ok(Response::ok(json!({
+        "puzzle": rated.puzzle.to_line(),
+        "solution": rated.solution.to_line(),
+        "difficulty": rated.rating.difficulty.to_string(),
+        "clues": rated.rating.clue_count,
+    })))
+}
+
+fn solve(params: &Map<String, Value>, deadline: Instant) -> ApiResult {
+    let puzzle = parse_puzzle(params)?;
+    let solution = find_solution_before(&puzzle, deadline)?
+        .ok_or_else(|| Response::error(422, "the puzzle has no solution"))?;
+    Ok(Response::ok(json!({
+        "solution": solution.to_line(),
+        "unique": count_solutions_before(&puzzle, 2, deadline)? == 1,
+    })))
+}
+
+fn count(params: &Map<String, Value>, deadline: Instant) -> ApiResult {
+    let puzzle = parse_puzzle(params)?;
+    let limit = parse_u64_param(params, "limit")?.unwrap_or(2) as usize;
+    if !(1..=MAX_COUNT_LIMIT).contains(&limit) {
+        return Err(Response::error(
+            400,
+            &format!("`limit` must be between 1 and {}", MAX_COUNT_LIMIT),
+        ));
+    }
+    let solutions = count_solutions_before(&puzzle, limit, deadline)?;
+    Ok(Response::ok(json!({
+        "solutions": solutions,
+        "limit_reached": solutions >= limit,
+    })))
+}
+
+fn rate(params: &Map<String, Value>, deadline: Instant) -> ApiResult {
+    let puzzle = parse_puzzle(params)?;
+    let rating = rate_puzzle_before(&puzzle, deadline)?
+        .ok_or_else(|| Response::error(422, "the puzzle does not have a unique solution"))?;
+    Ok(Response::ok(json!({
+        "difficulty": rating.difficulty.to_string(),
+        "clues": rating.clue_count,
+        "hardest_technique": rating.hardest_technique.map(|t| t.to_string()),
+        "steps": rating.steps,
+    })))
+}
+
+fn hint(params: &Map<String, Value>, deadline: Instant) -> ApiResult {
+    let puzzle = parse_puzzle(params)?;
+    if puzzle.is_complete() {
+        return Err(Response::error(422, "the puzzle is already complete"));
+    }
+    let hint = find_hint_before(&puzzle, deadline)?
+        .ok_or_else(|| Response::error(422, "the puzzle has no solution"))?;
+    let body = match hint {
+        Hint::Logic(steps) => json!({
+            "kind": "logic",
+            "steps": steps.iter().map(step_json).collect::<Vec<_>>(),
+        }),
+        Hint::Reveal { row, col, value } => json!({
+            "kind": "reveal",
+            "row": row,
+            "col": col,
+            "value": value,
+        }),
+    };
+    Ok(Response::ok(body))
+}
+
+fn validate(params: &Map<String, Value>, deadline: Instant) -> ApiResult {
+    let puzzle = parse_puzzle(params)?;
+    let problem = if !puzzle.is_compatible() {
+        Some("conflicting clues")
+    } else {
+        match count_solutions_before(&puzzle, 2, deadline)? {
+            0 => Some("no solution"),
+            1 => None,
+            _ => Some("multiple solutions"),
+        }
+    };
+    Ok(Response::ok(json!({
+        "valid": problem.is_none(),
+        "problem": problem,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    /// Far enough for every request of the tests to finish.
+    fn deadline() -> Instant {
+        Instant::now() + std::time::Duration::from_secs(60)
+    }
+
+    fn post(path: &str, body: Value) -> Response {
+        handle("POST", path, body.to_string().as_bytes(), deadline())
+    }
+
+    #[test]
+    fn test_generate_is_seeded() {
+        let params = json!({ "difficulty": "medium", "seed": 7, "symmetry": "rotational180" });
+        let response = post("/generate", params.clone());
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["difficulty"], "medium");
+        assert_eq!(post("/generate", params), response);
+        assert_eq!(
+            post("/generate", json!({ "difficulty": "impossible" })).status,
+            400
+        );
+    }
+
+    #[test]
+    fn test_solve_count_rate_validate() {
+        let response = post("/solve", json!({ "puzzle": PUZZLE }));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["unique"], true);
+        assert_eq!(response.body["solution"].as_str().unwrap().len(), 81);
+
+        let empty = ".".repeat(81);
+        let response = post("/count", json!({ "puzzle": empty, "limit": 5 }));
+        assert_eq!(
+            response.body,
+            json!({ "solutions": 5, "limit_reached": true })
+        );
+        assert_eq!(
+            post("/count", json!({ "puzzle": empty, "limit": 0 })).status,
+            400
+        );
+
+        let response = post("/rate", json!({ "puzzle": PUZZLE }));
+        assert_eq!(response.body["difficulty"], "easy");
+        assert_eq!(post("/rate", json!({ "puzzle": empty })).status, 422);
+
+        let response = post("/validate", json!({ "puzzle": empty }));
+        assert_eq!(
+            response.body,
+            json!({ "valid": false, "problem": "multiple solutions" })
+        );
+    }
+
+    #[test]
+    fn test_hint() {
+        let response = post("/hint", json!({ "puzzle": PUZZLE }));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["kind"], "logic");
+        let response = post("/hint", json!({ "puzzle": ".".repeat(81) }));
+        assert_eq!(response.body["kind"], "reveal");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(handle("GET", "/health", b"", deadline()).status, 200);
+        assert_eq!(handle("GET", "/solve", b"", deadline()).status, 405);
+        assert_eq!(handle("POST", "/unknown", b"{}", deadline()).status, 404);
+        assert_eq!(
+            handle("POST", "/solve", b"not json", deadline()).status,
+            400
+        );
+        assert_eq!(handle("POST", "/solve", b"[]", deadline()).status, 400);
+        assert_eq!(post("/solve", json!({ "puzzle": "123" })).status, 400);
+        assert_eq!(post("/solve", json!({})).status, 400);
+    }
+}
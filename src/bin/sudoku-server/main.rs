@@ -0,0 +1,228 @@
+mod api;
+
+use api::Response;
+use clap::Parser;
+use std::io::Read;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Request, Server};
+
+#[derive(Parser)]
+#[command(name = "sudoku-server")]
+#[command(
+    about = "HTTP JSON API to generate, solve, count, rate, hint and validate Sudoku puzzles"
+)]
+struct Cli {
+    /// Address to listen on. Keep the default to only accept local clients.
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to listen on, or 0 for any free port
+    #[arg(short, long, default_value_t = 8080)]
+    port: u16,
+
+    /// Largest accepted request body, in bytes
+    #[arg(long, default_value_t = 16 * 1024)]
+    max_body_bytes: usize,
+
+    /// Time limit of each request, in milliseconds
+    #[arg(long, default_value_t = 2000)]
+    timeout_ms: u64,
+
+    /// Number of threads serving requests, which is also the largest number of requests
+    /// computed at once
+    #[arg(short = 'j', long, default_value_t = 4)]
+    threads: usize,
+}
+
+/**
+ * Limits applied to every request.
+ */
+#[derive(Clone, Copy, Debug)]
+struct Limits {
+    max_body_bytes: usize,
+    timeout: Duration,
+}
+
+/**
+ * Read the body of a request, up to `max_body_bytes`.
+ *
+ * # Returns
+ * The body, or a 413 response if it is too large.
+ */
+fn read_body(request: &mut Request, max_body_bytes: usize) -> Result<Vec<u8>, Response> {
+    let too_large = || {
+        Response::error(
+            413,
+            &format!("the body is larger than {} bytes", max_body_bytes),
+        )
+    };
+    if request
+        .body_length()
+        .is_some_and(|len| len > max_body_bytes)
+    {
+        return Err(too_large());
+    }
+    let mut body = vec![];
+    request
+        .as_reader()
+        .take(max_body_bytes as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| Response::error(400, &format!("cannot read the body: {}", e)))?;
+    if body.len() > max_body_bytes {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+/**
+ * Run `api::handle` on the current serving thread and give up after `timeout`.
+ *
+ * The searches of the request check the deadline and stop on their own, so a request that
+ * times out is answered with a 504 response and leaves nothing running behind it.
+ */
+fn handle_with_timeout(method: &str, path: &str, body: &[u8], timeout: Duration) -> Response {
+    api::handle(method, path, body, Instant::now() + timeout)
+}
+
+fn respond(mut request: Request, limits: Limits) {
+    let method = request.method().to_string();
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let response = match read_body(&mut request, limits.max_body_bytes) {
+        Ok(body) => handle_with_timeout(&method, &path, &body, limits.timeout),
+        Err(response) => response,
+    };
+    eprintln!("{} {} {}", method, path, response.status);
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("the header is valid");
+    let http_response = tiny_http::Response::from_string(response.body.to_string())
+        .with_status_code(response.status)
+        .with_header(content_type);
+    if let Err(err) = request.respond(http_response) {
+        eprintln!("cannot send the response: {}", err);
+    }
+}
+
+/**
+ * Serve requests on `threads` threads until the server is unblocked.
+ *
+ * Each request is computed on the thread that received it, so at most `threads`
+ * computations run at once and further requests wait in the queue of the server.
+ */
+fn serve(server: Arc<Server>, threads: usize, limits: Limits) {
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            let server = &server;
+            scope.spawn(move || {
+                while let Ok(request) = server.recv() {
+                    respond(request, limits);
+                }
+            });
+        }
+    });
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let server = match Server::http((cli.host.as_str(), cli.port)) {
+        Ok(server) => Arc::new(server),
+        Err(err) => {
+            eprintln!("error: cannot listen on {}:{}: {}", cli.host, cli.port, err);
+            return ExitCode::from(2);
+        }
+    };
+    eprintln!("Listening on http://{}", server.server_addr());
+    let limits = Limits {
+        max_body_bytes: cli.max_body_bytes,
+        timeout: Duration::from_millis(cli.timeout_ms),
+    };
+    serve(server, cli.threads, limits);
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    /**
+     * Send a raw HTTP request and return the status code and the body of the response.
+     */
+    fn send(addr: &str, request: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or("").to_string();
+        (status, body)
+    }
+
+    fn post(addr: &str, path: &str, body: &str) -> (u16, String) {
+        send(
+            addr,
+            &format!(
+                "POST {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                path,
+                body.len(),
+                body
+            ),
+        )
+    }
+
+    #[test]
+    fn test_server() {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let addr = server.server_addr().to_ip().unwrap().to_string();
+        let limits = Limits {
+            max_body_bytes: 256,
+            timeout: Duration::from_secs(10),
+        };
+        let worker = {
+            let server = server.clone();
+            thread::spawn(move || serve(server, 2, limits))
+        };
+
+        let (status, body) = send(
+            &addr,
+            "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        );
+        assert_eq!((status, body.as_str()), (200, r#"{"status":"ok"}"#));
+
+        let puzzle = format!(r#"{{"puzzle":"{}"}}"#, ".".repeat(81));
+        let (status, body) = post(&addr, "/validate", &puzzle);
+        assert_eq!(status, 200);
+        assert!(body.contains("multiple solutions"));
+
+        let (status, _) = post(&addr, "/generate", &" ".repeat(257));
+        assert_eq!(status, 413);
+
+        // Wake up both serving threads.
+        server.unblock();
+        server.unblock();
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_timeout() {
+        let start = Instant::now();
+        let response = handle_with_timeout(
+            "POST",
+            "/generate",
+            br#"{"difficulty":"expert"}"#,
+            Duration::ZERO,
+        );
+        assert_eq!(response.status, 504);
+        let body = format!(r#"{{"puzzle":"{}","limit":10000}}"#, ".".repeat(81));
+        let response =
+            handle_with_timeout("POST", "/count", body.as_bytes(), Duration::from_millis(1));
+        assert_eq!(response.status, 504);
+        // The computations stopped instead of running on in the background.
+        assert!(start.elapsed() < Duration::from_secs(2));
+        let response = handle_with_timeout("GET", "/health", b"", Duration::ZERO);
+        assert_eq!(response.status, 200);
+    }
+}
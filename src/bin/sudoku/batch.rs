@@ -0,0 +1,266 @@
+use crate::commands::Outcome;
+use clap::{Args, ValueEnum};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use sudoku_lib::{
+    BankEntry, Difficulty, PuzzleBank, Symmetry, create_rated_symmetric_matrix,
+    create_rated_symmetric_matrix_before,
+};
+
+/**
+ * File formats the generated puzzles can be written in.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BatchFormat {
+    /// One puzzle per line, 81 characters with `.` for empty cells
+    Line,
+    /// Comma separated puzzle, solution, difficulty, clue count and canonical hash
+    Csv,
+    /// A puzzle bank file, as read by `tui-game --bank`. Existing puzzles are kept.
+    Bank,
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// Number of puzzles to generate
+    #[arg(short = 'n', long)]
+    count: usize,
+
+    /// Difficulty of every generated puzzle
+    #[arg(short, long, default_value_t = Difficulty::Easy)]
+    difficulty: Difficulty,
+
+    /// Symmetry of the clue layouts
+    #[arg(long, default_value_t = Symmetry::None)]
+    symmetry: Symmetry,
+
+    /// Number of worker threads. Defaults to the number of CPUs.
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// File to write the puzzles to
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Format of the output file
+    #[arg(long, value_enum, default_value_t = BatchFormat::Bank)]
+    format: BatchFormat,
+
+    /// Do not report progress
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Give up after generating this many puzzles, counting the ones dropped for their
+    /// difficulty or as duplicates. Defaults to 1000 per requested puzzle.
+    #[arg(long)]
+    max_attempts: Option<usize>,
+
+    /// Give up after this many seconds
+    #[arg(long)]
+    timeout: Option<f64>,
+}
+
+/**
+ * Progress of a batch, reported on stderr when it is a terminal.
+ */
+struct Progress {
+    start: Instant,
+    last_report: Instant,
+    enabled: bool,
+}
+
+impl Progress {
+    fn new(enabled: bool) -> Self {
+        let now = Instant::now();
+        Progress {
+            start: now,
+            last_report: now,
+            enabled: enabled && io::stderr().is_terminal(),
+        }
+    }
+
+    fn report(&mut self, done: usize, total: usize, duplicates: usize, force: bool) {
+        if !self.enabled || (!force && self.last_report.elapsed() < Duration::from_millis(200)) {
+            return;
+        }
+        self.last_report = Instant::now();
+        let rate = done as f64 / self.start.elapsed().as_secs_f64().max(1e-9);
+        eprint!(
+            "\r{}/{} puzzles, {} duplicates skipped, {:.1} puzzles/s ",
+            done, total, duplicates, rate
+        );
+        let _ = io::stderr().flush();
+        if force {
+            eprintln!();
+        }
+    }
+}
+
+/**
+ * Generate puzzles on worker threads until `args.count` distinct ones are collected, or
+ * the attempt or time budget runs out.
+ *
+ * Workers only keep puzzles that match the difficulty exactly; the collecting thread
+ * drops puzzles equivalent to one already collected or already in `known_hashes`.
+ * Workers stop generating at the deadline of `--timeout`, so the batch ends on time.
+ */
+fn generate(args: &BatchArgs, known_hashes: &HashSet<u64>) -> (Vec<BankEntry>, usize) {
+    let threads = args
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1);
+    let source = format!(
+        "sudoku batch, difficulty {}, symmetry {}",
+        args.difficulty, args.symmetry
+    );
+    let max_attempts = args.max_attempts.unwrap_or(args.count.saturating_mul(1000));
+    let deadline = args
+        .timeout
+        .map(|secs| Instant::now() + Duration::from_secs_f64(secs.max(0.0)));
+    let stop = AtomicBool::new(false);
+    let attempts = AtomicUsize::new(0);
+    let mut progress = Progress::new(!args.quiet);
+    let mut seen = known_hashes.clone();
+    let mut entries = vec![];
+    let mut duplicates = 0;
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (stop, attempts, source) = (&stop, &attempts, &source);
+            scope.spawn(move || {
+                let mut rng = rand::rng();
+                while !stop.load(Ordering::Relaxed)
+                    && attempts.fetch_add(1, Ordering::Relaxed) < max_attempts
+                {
+                    let rated = match deadline {
+                        Some(deadline) => match create_rated_symmetric_matrix_before(
+                            args.difficulty,
+                            args.symmetry,
+                            &mut rng,
+                            deadline,
+                        ) {
+                            Ok(rated) => rated,
+                            Err(_) => break,
+                        },
+                        None => {
+                            create_rated_symmetric_matrix(args.difficulty, args.symmetry, &mut rng)
+                        }
+                    };
+                    if rated.rating.difficulty != args.difficulty {
+                        continue;
+                    }
+                    // Hashing takes a while, and the puzzle would not be collected anyway.
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        break;
+                    }
+                    if tx.send(BankEntry::new(rated, source)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        while entries.len() < args.count {
+            let received = match deadline {
+                Some(deadline) => {
+                    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => rx.recv().map_err(mpsc::RecvTimeoutError::from),
+            };
+            let Ok(entry) = received else {
+                break;
+            };
+            if seen.insert(entry.canonical_hash) {
+                entries.push(entry);
+            } else {
+                duplicates += 1;
+            }
+            progress.report(entries.len(), args.count, duplicates, false);
+        }
+        stop.store(true, Ordering::Relaxed);
+    });
+    progress.report(entries.len(), args.count, duplicates, true);
+    (entries, duplicates)
+}
+
+fn write_entries(args: &BatchArgs, entries: &[BankEntry]) -> io::Result<()> {
+    let mut content = String::new();
+    if args.format == BatchFormat::Csv {
+        content.push_str("puzzle,solution,difficulty,clues,canonical_hash\n");
+    }
+    for entry in entries {
+        let line = match args.format {
+            BatchFormat::Csv => format!(
+                "{},{},{},{},{:016x}",
+                entry.puzzle.to_line(),
+                entry.solution.to_line(),
+                entry.rating.difficulty,
+                entry.clue_count(),
+                entry.canonical_hash
+            ),
+            _ => entry.puzzle.to_line(),
+        };
+        content.push_str(&line);
+        content.push('\n');
+    }
+    fs::write(&args.output, content)
+}
+
+/**
+ * Generate the puzzles and write them.
+ *
+ * # Returns
+ * `Outcome::Failure` when the budget ran out before `args.count` puzzles were found. The
+ * puzzles found are written anyway.
+ */
+pub fn run(args: BatchArgs) -> Result<Outcome, String> {
+    let start = Instant::now();
+    let mut bank = match args.format {
+        BatchFormat::Bank => Some(PuzzleBank::open(&args.output).map_err(|e| e.to_string())?),
+        _ => None,
+    };
+    let known_hashes: HashSet<u64> = bank
+        .iter()
+        .flat_map(|bank| bank.entries().iter().map(|entry| entry.canonical_hash))
+        .collect();
+
+    let (entries, duplicates) = generate(&args, &known_hashes);
+    let count = entries.len();
+    let write_result = match bank.as_mut() {
+        Some(bank) => {
+            for entry in entries {
+                bank.insert(entry);
+            }
+            bank.save()
+        }
+        None => write_entries(&args, &entries),
+    };
+    write_result.map_err(|e| format!("cannot write {}: {}", args.output.display(), e))?;
+    if !args.quiet {
+        eprintln!(
+            "Generated {} {} puzzles ({} duplicates skipped) in {:.1}s, written to {}",
+            count,
+            args.difficulty,
+            duplicates,
+            start.elapsed().as_secs_f64(),
+            args.output.display()
+        );
+    }
+    if count < args.count {
+        eprintln!(
+            "error: only {} of {} {} puzzles were generated before the budget ran out",
+            count, args.count, args.difficulty
+        );
+        return Ok(Outcome::Failure);
+    }
+    Ok(Outcome::Success)
+}
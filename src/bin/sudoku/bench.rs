@@ -0,0 +1,90 @@
+use clap::Args;
+use serde_json::{Value, json};
+use std::time::Duration;
+use sudoku_lib::{BenchResult, SolverBackend, bundled_puzzle_sets, run_benchmark};
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Backends to run, all of them when omitted: backtracking, bitmask, bitmask-count, logic
+    #[arg(short, long)]
+    backend: Vec<SolverBackend>,
+
+    /// Bundled puzzle sets to run on, all of them when omitted: easy, hard, 17-clue, pathological
+    #[arg(short, long)]
+    set: Vec<String>,
+
+    /// Number of times each set is run
+    #[arg(short, long, default_value_t = 1)]
+    repeat: usize,
+
+    /// Write one JSON object per backend and set instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn to_json(result: &BenchResult) -> Value {
+    json!({
+        "backend": result.backend.to_string(),
+        "set": result.set,
+        "runs": result.runs,
+        "solved": result.solved,
+        "puzzles_per_second": result.puzzles_per_second(),
+        "median_ms": millis(result.median_latency),
+        "p99_ms": millis(result.p99_latency),
+        "nodes": result.nodes,
+        "nodes_per_puzzle": result.nodes_per_puzzle(),
+    })
+}
+
+pub fn run(args: BenchArgs) -> Result<(), String> {
+    let backends = if args.backend.is_empty() {
+        SolverBackend::ALL.to_vec()
+    } else {
+        args.backend
+    };
+    let sets = bundled_puzzle_sets();
+    if let Some(unknown) = args
+        .set
+        .iter()
+        .find(|name| !sets.iter().any(|s| &&s.name == name))
+    {
+        return Err(format!(
+            "unknown puzzle set `{}`, expected one of: easy, hard, 17-clue, pathological",
+            unknown
+        ));
+    }
+    if !args.json {
+        println!(
+            "{:<14} {:<13} {:>6} {:>6} {:>11} {:>11} {:>11} {:>13}",
+            "backend", "set", "runs", "solved", "puzzles/s", "median ms", "p99 ms", "nodes/puzzle"
+        );
+    }
+    for backend in backends {
+        for set in sets
+            .iter()
+            .filter(|set| args.set.is_empty() || args.set.contains(&set.name))
+        {
+            let result = run_benchmark(backend, set, args.repeat.max(1));
+            if args.json {
+                println!("{}", to_json(&result));
+            } else {
+                println!(
+                    "{:<14} {:<13} {:>6} {:>6} {:>11.1} {:>11.3} {:>11.3} {:>13.1}",
+                    result.backend.to_string(),
+                    result.set,
+                    result.runs,
+                    result.solved,
+                    result.puzzles_per_second(),
+                    millis(result.median_latency),
+                    millis(result.p99_latency),
+                    result.nodes_per_puzzle()
+                );
+            }
+        }
+    }
+    Ok(())
+}
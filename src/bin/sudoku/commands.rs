@@ -0,0 +1,377 @@
+use clap::Args;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use sudoku_lib::{
+    Difficulty, PuzzleFormat, PuzzleReader, ReadError, SudokuMatrix, Symmetry, canonicalize,
+    count_solutions, create_rated_symmetric_matrix, find_solution, hash_canonical, is_minimal,
+    process_in_parallel, rate_puzzle,
+};
+
+/**
+ * Where and how to read the input puzzles.
+ */
+#[derive(Args)]
+pub struct InputArgs {
+    /// Files to read puzzles from. Standard input is read when none is given, or for `-`.
+    files: Vec<PathBuf>,
+
+    /// Format of the input, detected from the first puzzle when omitted
+    #[arg(long)]
+    from: Option<PuzzleFormat>,
+
+    /// Number of worker threads. Defaults to the number of CPUs.
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Print the number of puzzles processed and the throughput on stderr
+    #[arg(long)]
+    stats: bool,
+}
+
+/**
+ * How to write the results.
+ */
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Format of the puzzles in text output
+    #[arg(long, default_value_t = PuzzleFormat::Line)]
+    pub to: PuzzleFormat,
+
+    /// Write one JSON object per puzzle and line instead of text
+    #[arg(long)]
+    pub json: bool,
+}
+
+/**
+ * Result of processing one puzzle.
+ */
+pub struct Report {
+    /// Text output, written as is.
+    text: String,
+    /// Fields of the JSON output, after the index of the puzzle.
+    json: Vec<(&'static str, Value)>,
+    /// Whether the puzzle was processed successfully, such as solved or found valid.
+    ok: bool,
+}
+
+impl Report {
+    fn new(text: String, json: Vec<(&'static str, Value)>, ok: bool) -> Self {
+        Report { text, json, ok }
+    }
+
+    /**
+     * A failed report whose text output is a comment, so that text output with puzzles
+     * can still be read back.
+     */
+    fn failure(index: usize, message: &str, json: Vec<(&'static str, Value)>) -> Self {
+        Report::new(format!("# puzzle {}: {}", index, message), json, false)
+    }
+}
+
+/**
+ * Outcome of a command that ran to completion.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every puzzle was processed successfully.
+    Success,
+    /// Some puzzle is unsolvable, invalid or not unique, or a batch fell short.
+    Failure,
+}
+
+/**
+ * Writes the reports of a command in order, in text or JSON.
+ */
+struct ReportWriter {
+    out: io::BufWriter<io::Stdout>,
+    options: OutputArgs,
+    count: usize,
+    outcome: Outcome,
+}
+
+impl ReportWriter {
+    fn new(options: OutputArgs) -> Self {
+        ReportWriter {
+            out: io::BufWriter::new(io::stdout()),
+            options,
+            count: 0,
+            outcome: Outcome::Success,
+        }
+    }
+
+    fn write(&mut self, report: Report) -> io::Result<()> {
+        self.count += 1;
+        if !report.ok {
+            self.outcome = Outcome::Failure;
+        }
+        if self.options.json {
+            let fields = [("index", Value::from(self.count))]
+                .into_iter()
+                .chain(report.json)
+                .map(|(key, value)| (key.to_string(), value));
+            writeln!(self.out, "{}", Value::Object(fields.collect()))
+        } else {
+            if self.options.to == PuzzleFormat::Grid && self.count > 1 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "{}", report.text)
+        }
+    }
+
+    fn finish(mut self) -> io::Result<Outcome> {
+        self.out.flush()?;
+        Ok(self.outcome)
+    }
+}
+
+/// An input with its name for error messages.
+type NamedInput = (String, Box<dyn BufRead>);
+
+fn open_inputs(input: &InputArgs) -> Result<Vec<NamedInput>, String> {
+    if input.files.is_empty() {
+        return Ok(vec![("<stdin>".to_string(), Box::new(io::stdin().lock()))]);
+    }
+    input
+        .files
+        .iter()
+        .map(|path| {
+            let name = path.display().to_string();
+            if name == "-" {
+                return Ok((name, Box::new(io::stdin().lock()) as Box<dyn BufRead>));
+            }
+            let file = File::open(path).map_err(|e| format!("cannot open {}: {}", name, e))?;
+            Ok((name, Box::new(BufReader::new(file)) as Box<dyn BufRead>))
+        })
+        .collect()
+}
+
+/**
+ * Read every input puzzle in turn, run `f` on each across worker threads, and write the
+ * reports in input order.
+ *
+ * `f` receives the 1-based index of the puzzle among all inputs. Malformed puzzles get a
+ * failed report with the parse error instead.
+ */
+pub fn process<F>(input: &InputArgs, output: OutputArgs, f: F) -> Result<Outcome, String>
+where
+    F: Fn(usize, &SudokuMatrix) -> Report + Sync,
+{
+    let mut writer = ReportWriter::new(output);
+    let mut index = 0;
+    // A malformed record is reported in its place, but the input cannot be read past an
+    // I/O error.
+    let puzzles = open_inputs(input)?
+        .into_iter()
+        .flat_map(|(name, reader)| {
+            PuzzleReader::new(reader, input.from).map(move |puzzle| match puzzle {
+                Ok(puzzle) => Ok(Ok(puzzle)),
+                Err(e @ ReadError::Parse { .. }) => Ok(Err(format!("{}: {}", name, e))),
+                Err(e) => Err(format!("{}: {}", name, e)),
+            })
+        })
+        .map(|puzzle| {
+            index += 1;
+            puzzle.map(|puzzle| (index, puzzle))
+        });
+    let stats = process_in_parallel(
+        puzzles,
+        input.threads.unwrap_or(0),
+        |(index, puzzle)| match puzzle {
+            Ok(puzzle) => f(*index, puzzle),
+            Err(message) => {
+                Report::failure(*index, message, vec![("error", message.as_str().into())])
+            }
+        },
+        |report| {
+            writer
+                .write(report)
+                .map_err(|e| format!("cannot write output: {}", e))
+        },
+    )?;
+    let outcome = writer
+        .finish()
+        .map_err(|e| format!("cannot write output: {}", e))?;
+    if input.stats {
+        eprintln!(
+            "Processed {} puzzles in {:.2}s, {:.1} puzzles/s on {} threads",
+            stats.items,
+            stats.elapsed.as_secs_f64(),
+            stats.throughput(),
+            stats.threads
+        );
+    }
+    Ok(outcome)
+}
+
+pub fn solve(index: usize, puzzle: &SudokuMatrix, to: PuzzleFormat) -> Report {
+    let Some(solution) = find_solution(puzzle) else {
+        return Report::failure(
+            index,
+            "no solution",
+            vec![("solved", false.into()), ("solution", Value::Null)],
+        );
+    };
+    let solutions = count_solutions(puzzle, 2);
+    Report::new(
+        to.format(&solution),
+        vec![
+            ("solved", true.into()),
+            ("solution", solution.to_line().into()),
+            ("unique", (solutions == 1).into()),
+        ],
+        solutions == 1,
+    )
+}
+
+pub fn count(puzzle: &SudokuMatrix, limit: usize) -> Report {
+    let solutions = count_solutions(puzzle, limit);
+    let json = vec![
+        ("solutions", solutions.into()),
+        ("limit_reached", (solutions >= limit).into()),
+    ];
+    let text = if solutions >= limit {
+        format!("{}+", solutions)
+    } else {
+        solutions.to_string()
+    };
+    Report::new(text, json, solutions > 0)
+}
+
+pub fn rate(index: usize, puzzle: &SudokuMatrix) -> Report {
+    let Some(rating) = rate_puzzle(puzzle) else {
+        return Report::failure(
+            index,
+            "no unique solution",
+            vec![("difficulty", Value::Null)],
+        );
+    };
+    let technique = rating.hardest_technique.map(|t| t.to_string());
+    Report::new(
+        format!(
+            "{}\t{}\t{}\t{}",
+            rating.difficulty,
+            rating.clue_count,
+            technique.as_deref().unwrap_or("-"),
+            rating.steps
+        ),
+        vec![
+            ("difficulty", rating.difficulty.to_string().into()),
+            ("clues", rating.clue_count.into()),
+            ("hardest_technique", technique.into()),
+            ("steps", rating.steps.into()),
+        ],
+        true,
+    )
+}
+
+pub fn validate(puzzle: &SudokuMatrix, require_minimal: bool) -> Report {
+    let problem = if !puzzle.is_compatible() {
+        Some("conflicting clues")
+    } else {
+        match count_solutions(puzzle, 2) {
+            0 => Some("no solution"),
+            1 if require_minimal && !is_minimal(puzzle) => Some("not minimal"),
+            1 => None,
+            _ => Some("multiple solutions"),
+        }
+    };
+    let text = match problem {
+        Some(problem) => format!("invalid: {}", problem),
+        None => "valid".to_string(),
+    };
+    Report::new(
+        text,
+        vec![
+            ("valid", problem.is_none().into()),
+            ("problem", problem.into()),
+        ],
+        problem.is_none(),
+    )
+}
+
+pub fn canonical(puzzle: &SudokuMatrix, to: PuzzleFormat) -> Report {
+    let canonical = canonicalize(puzzle);
+    let hash = format!("{:016x}", hash_canonical(&canonical));
+    Report::new(
+        to.format(&canonical),
+        vec![
+            ("canonical", canonical.to_line().into()),
+            ("hash", hash.into()),
+        ],
+        true,
+    )
+}
+
+pub fn convert(puzzle: &SudokuMatrix, to: PuzzleFormat) -> Report {
+    Report::new(
+        to.format(puzzle),
+        vec![
+            ("puzzle", puzzle.to_line().into()),
+            ("clues", puzzle.filled_count().into()),
+        ],
+        true,
+    )
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    /// Number of puzzles to generate
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+
+    /// Difficulty of the puzzles
+    #[arg(short, long, default_value_t = Difficulty::Easy)]
+    difficulty: Difficulty,
+
+    /// Symmetry of the clue layouts
+    #[arg(long, default_value_t = Symmetry::None)]
+    symmetry: Symmetry,
+
+    /// Seed of the random generator, to get the same puzzles on every run
+    #[arg(long)]
+    seed: Option<u64>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn generate(args: GenerateArgs) -> Result<Outcome, String> {
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let to = args.output.to;
+    let mut writer = ReportWriter::new(args.output);
+    for index in 1..=args.count {
+        let rated = create_rated_symmetric_matrix(args.difficulty, args.symmetry, &mut rng);
+        // The generator falls back to the closest difficulty it found, which is still
+        // written but fails the command.
+        let matches = rated.rating.difficulty == args.difficulty;
+        if !matches {
+            eprintln!(
+                "warning: puzzle {} is {} instead of {}",
+                index, rated.rating.difficulty, args.difficulty
+            );
+        }
+        let report = Report::new(
+            to.format(&rated.puzzle),
+            vec![
+                ("puzzle", rated.puzzle.to_line().into()),
+                ("solution", rated.solution.to_line().into()),
+                ("difficulty", rated.rating.difficulty.to_string().into()),
+                ("clues", rated.rating.clue_count.into()),
+            ],
+            matches,
+        );
+        writer
+            .write(report)
+            .map_err(|e| format!("cannot write output: {}", e))?;
+    }
+    writer
+        .finish()
+        .map_err(|e| format!("cannot write output: {}", e))
+}
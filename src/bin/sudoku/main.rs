@@ -0,0 +1,156 @@
+mod batch;
+mod bench;
+mod commands;
+
+use clap::{Parser, Subcommand};
+use commands::{GenerateArgs, InputArgs, Outcome, OutputArgs};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "sudoku")]
+#[command(about = "Command line tools for Sudoku puzzles")]
+#[command(
+    after_help = "Exit status: 0 on success; 1 if some puzzle is malformed, has no solution \
+                  (solve, count, rate, validate), several solutions (solve, rate, validate), \
+                  conflicting clues or removable clues with --minimal (validate), another \
+                  difficulty than asked (generate), or if batch generated fewer puzzles than \
+                  asked; 2 on errors."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve puzzles
+    Solve {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Count the solutions of puzzles
+    Count {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        /// Stop counting at this number of solutions
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+    },
+    /// Generate rated puzzles with a unique solution
+    Generate(GenerateArgs),
+    /// Rate the difficulty of puzzles: difficulty, clues, hardest technique and steps
+    Rate {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Check that puzzles have no conflict and a unique solution
+    Validate {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        /// Also require that removing any clue breaks the uniqueness
+        #[arg(long)]
+        minimal: bool,
+    },
+    /// Compute the canonical form of puzzles, shared by all equivalent puzzles
+    Canonicalize {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Convert puzzles between formats
+    Convert {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Generate many rated, unique puzzles across worker threads
+    Batch(batch::BatchArgs),
+    /// Measure the solver backends on the bundled puzzle sets
+    Bench(bench::BenchArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Solve { input, output } => {
+            let to = output.to;
+            commands::process(&input, output, |index, puzzle| {
+                commands::solve(index, puzzle, to)
+            })
+        }
+        Command::Count {
+            input,
+            output,
+            limit,
+        } => commands::process(&input, output, |_, puzzle| {
+            commands::count(puzzle, limit.max(1))
+        }),
+        Command::Generate(args) => commands::generate(args),
+        Command::Rate { input, output } => commands::process(&input, output, commands::rate),
+        Command::Validate {
+            input,
+            output,
+            minimal,
+        } => commands::process(&input, output, |_, puzzle| {
+            commands::validate(puzzle, minimal)
+        }),
+        Command::Canonicalize { input, output } => {
+            let to = output.to;
+            commands::process(&input, output, |_, puzzle| commands::canonical(puzzle, to))
+        }
+        Command::Convert { input, output } => {
+            let to = output.to;
+            commands::process(&input, output, |_, puzzle| commands::convert(puzzle, to))
+        }
+        Command::Batch(args) => batch::run(args),
+        Command::Bench(args) => bench::run(args).map(|()| Outcome::Success),
+    };
+    match result {
+        Ok(Outcome::Success) => ExitCode::SUCCESS,
+        Ok(Outcome::Failure) => ExitCode::from(1),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arguments() {
+        let cli = Cli::try_parse_from(["sudoku", "solve", "--json", "-j", "2", "a.txt"]).unwrap();
+        assert!(matches!(cli.command, Command::Solve { ref output, .. } if output.json));
+
+        let cli = Cli::try_parse_from(["sudoku", "count", "--limit", "5"]).unwrap();
+        assert!(matches!(cli.command, Command::Count { limit: 5, .. }));
+
+        let cli = Cli::try_parse_from(["sudoku", "validate", "--minimal", "--to", "grid"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Validate { minimal: true, .. }
+        ));
+
+        for args in [
+            &["sudoku"][..],
+            &["sudoku", "unknown"],
+            &["sudoku", "solve", "--to", "xml"],
+            &["sudoku", "count", "--limit", "-1"],
+            &["sudoku", "batch", "--output", "out.txt"],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{:?}", args);
+        }
+    }
+}
@@ -1,90 +1,460 @@
+use crate::menu::{MenuAction, NewGame, NewGameMenu};
+use crate::samurai::SamuraiWidget;
+use crate::storage;
+use clap::ValueEnum;
+use rand::{SeedableRng, rngs::StdRng};
+use std::fs;
 use std::io;
-use sudoku_lib::{SudokuMatrix, create_matrix, solve_sudoku};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use sudoku_lib::{
+    CalendarDate, DiagonalDirection, Difficulty, Game, Hint, OutsideClues, SudokuMatrix, Symmetry,
+    create_matrix, create_rated_matrix, create_samurai_matrix, create_sandwich_matrix,
+    create_symmetric_matrix, daily_difficulty, daily_puzzle,
+};
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
 
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
-    layout::{Alignment, Constraint, Layout, Rect, Spacing},
+    layout::{Alignment, Constraint, Layout, Margin, Position, Rect, Spacing},
     style::{Color, Stylize},
     symbols::merge::MergeStrategy,
-    text::Line,
-    widgets::{Block, Paragraph, Widget},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget, Wrap},
 };
 
 /**
- * Widget to render the sudoku
+ * Widget to render the sudoku, and to play it with the cursor. The rules of the game are
+ * left to `Game`.
  */
 #[derive(Debug)]
 pub struct SudokuWidget {
-    matrix: SudokuMatrix,
-    is_original_matrix: [bool; 81],
+    game: Game,
     cursor_pos: usize,
+    /// Whether digit keys edit the notes instead of the values.
+    notes_mode: bool,
+    highlights: Highlights,
+    /// Whether digits differing from the solution are always colored.
+    show_mistakes: bool,
+    /// Until when the wrong digits are flagged after checking the board.
+    checked_until: Option<Instant>,
+    /// The last hint, with the board it was found for.
+    hint: Option<Box<(SudokuMatrix, Hint)>>,
 }
 
+/**
+ * Kinds of highlighting that help scanning the grid from the cursor.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Highlight {
+    /// Shade the row, column and box of the cursor
+    Peers,
+    /// Highlight the cells holding the digit under the cursor
+    Digits,
+    /// Highlight the notes of the digit under the cursor
+    Notes,
+    /// No highlighting
+    None,
+}
+
+/**
+ * The highlights turned on.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Highlights {
+    peers: bool,
+    digits: bool,
+    notes: bool,
+}
+
+impl Highlights {
+    pub fn new(kinds: &[Highlight]) -> Self {
+        Highlights {
+            peers: kinds.contains(&Highlight::Peers),
+            digits: kinds.contains(&Highlight::Digits),
+            notes: kinds.contains(&Highlight::Notes),
+        }
+    }
+}
+
+impl Default for Highlights {
+    fn default() -> Self {
+        Highlights {
+            peers: true,
+            digits: true,
+            notes: true,
+        }
+    }
+}
+
+const CURSOR_BG: Color = Color::DarkGray;
+const PEER_BG: Color = Color::Indexed(236);
+const SAME_DIGIT_BG: Color = Color::Blue;
+
+/**
+ * How long wrong digits stay flagged after checking the board.
+ */
+const CHECK_DURATION: Duration = Duration::from_secs(2);
+
+/**
+ * Height of the cells, borders included, when the terminal is tall enough to draw the
+ * notes as a 3x3 mini-grid.
+ */
+const TALL_CELL_HEIGHT: u16 = 5;
+
+/**
+ * Height of the cells, borders included, otherwise.
+ */
+const COMPACT_CELL_HEIGHT: u16 = 3;
+
 impl Widget for &SudokuWidget {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let centered_area = area.centered(Constraint::Length(37), Constraint::Length(19));
+        let (grid_area, cell_height) = self.grid_layout(area);
+        if let Some(clues) = self.game.clues() {
+            SudokuWidget::render_clues(clues, grid_area, cell_height, buf);
+        }
+        let cells = SudokuWidget::split_cells(grid_area, cell_height);
+
+        let (cursor_r, cursor_c) = (self.cursor_pos / 9, self.cursor_pos % 9);
+        let selected = Some(self.game.value(cursor_r, cursor_c)).filter(|v| *v != 0);
+        for (i, cell) in cells.into_iter().enumerate() {
+            let (r, c) = (i / 9, i % 9);
+            let note_highlight = selected.filter(|_| self.highlights.notes);
+            let mut lines = self.cell_lines(i, cell_height == TALL_CELL_HEIGHT, note_highlight);
+            let is_peer =
+                r == cursor_r || c == cursor_c || (r / 3 == cursor_r / 3 && c / 3 == cursor_c / 3);
+            let bg = if self.cursor_pos == i {
+                Some(CURSOR_BG)
+            } else if self.highlights.digits && selected.is_some_and(|v| v == self.game.value(r, c))
+            {
+                Some(SAME_DIGIT_BG)
+            } else if self.highlights.peers && is_peer {
+                Some(PEER_BG)
+            } else {
+                None
+            };
+            if let Some(bg) = bg {
+                lines = lines.into_iter().map(|line| line.bg(bg)).collect();
+            }
+            Paragraph::new(lines)
+                .block(Block::bordered().merge_borders(MergeStrategy::Exact))
+                .render(cell, buf);
+        }
+    }
+}
+
+impl SudokuWidget {
+    /**
+     * Place the grid in an area, leaving room around it for the outside clues.
+     *
+     * # Returns
+     * The area of the grid and the height of its cells, borders included.
+     */
+    fn grid_layout(&self, area: Rect) -> (Rect, u16) {
+        let clue_margin = if self.game.clues().is_some() { 2 } else { 0 };
+        let cell_height = if area.height >= 9 * (TALL_CELL_HEIGHT - 1) + 1 + clue_margin {
+            TALL_CELL_HEIGHT
+        } else {
+            COMPACT_CELL_HEIGHT
+        };
+        let grid_height = 9 * (cell_height - 1) + 1;
+        let grid_area = if self.game.clues().is_some() {
+            let outer_area =
+                area.centered(Constraint::Length(45), Constraint::Length(grid_height + 2));
+            Rect {
+                x: outer_area.x + 4,
+                y: outer_area.y + 1,
+                width: 37.min(outer_area.width.saturating_sub(4)),
+                height: grid_height.min(outer_area.height.saturating_sub(1)),
+            }
+        } else {
+            area.centered(Constraint::Length(37), Constraint::Length(grid_height))
+        };
+        (grid_area, cell_height)
+    }
+
+    /**
+     * Split the grid into the areas of its 81 cells in row-major order, where neighbors
+     * share their borders.
+     */
+    fn split_cells(grid_area: Rect, cell_height: u16) -> Vec<Rect> {
         let col_constraints = (0..9).map(|_| Constraint::Length(5));
-        let row_constraints = (0..9).map(|_| Constraint::Length(3));
+        let row_constraints = (0..9).map(|_| Constraint::Length(cell_height));
         let horizontal = Layout::horizontal(col_constraints).spacing(Spacing::Overlap(1));
         let vertical = Layout::vertical(row_constraints).spacing(Spacing::Overlap(1));
-        let rows = vertical.split(centered_area);
-        let cells = rows.iter().flat_map(|&row| horizontal.split(row).to_vec());
-
-        for (i, cell) in cells.enumerate() {
-            let x = i / 9;
-            let y = i % 9;
-            let v = self.matrix.get_value(x, y);
-            let v_text = if v == 0 {
-                "   ".to_string()
-            } else {
-                format!(" {} ", v)
+        let rows = vertical.split(grid_area);
+        rows.iter()
+            .flat_map(|&row| horizontal.split(row).to_vec())
+            .collect()
+    }
+
+    /**
+     * Return the areas of the 81 cells when the widget is rendered in an area.
+     */
+    pub fn cell_areas(&self, area: Rect) -> Vec<Rect> {
+        let (grid_area, cell_height) = self.grid_layout(area);
+        SudokuWidget::split_cells(grid_area, cell_height)
+    }
+
+    /**
+     * Move the cursor to a cell, given by its index in row-major order.
+     */
+    pub fn select_cell(&mut self, i: usize) {
+        if i < 81 {
+            self.cursor_pos = i;
+        }
+    }
+
+    /**
+     * Render the outside clues around the grid.
+     *
+     * Clues are placed on a ring of virtual cells around the grid, where row or
+     * column `-1` and `9` are just outside the border.
+     */
+    fn render_clues(clues: &OutsideClues, grid_area: Rect, cell_height: u16, buf: &mut Buffer) {
+        let mut slots: Vec<((isize, isize), String)> = vec![];
+        for idx in 0..9 {
+            let i = idx as isize;
+            for (pos, clue) in [
+                ((-1, i), clues.sandwich_cols[idx]),
+                ((-1, i), clues.skyscraper_top[idx]),
+                ((9, i), clues.skyscraper_bottom[idx]),
+                ((i, -1), clues.sandwich_rows[idx]),
+                ((i, -1), clues.skyscraper_left[idx]),
+                ((i, 9), clues.skyscraper_right[idx]),
+            ] {
+                if let Some(v) = clue {
+                    slots.push((pos, v.to_string()));
+                }
+            }
+        }
+        for clue in clues.little_killers.iter() {
+            let (dr, dc) = clue.direction().step();
+            let arrow = match clue.direction() {
+                DiagonalDirection::DownRight => "↘",
+                DiagonalDirection::DownLeft => "↙",
+                DiagonalDirection::UpRight => "↗",
+                DiagonalDirection::UpLeft => "↖",
             };
-            let v_line = if self.is_original_matrix[i] {
-                v_text.bold()
-            } else {
-                v_text.into()
+            let pos = (clue.start().0 as isize - dr, clue.start().1 as isize - dc);
+            slots.push((pos, format!("{}{}", arrow, clue.sum())));
+        }
+
+        for ((vr, vc), text) in slots {
+            let (x, width, alignment) = match vc {
+                -1 => (grid_area.x as isize - 4, 3, Alignment::Right),
+                9 => (grid_area.x as isize + 38, 4, Alignment::Left),
+                _ => (grid_area.x as isize + 4 * vc + 1, 3, Alignment::Center),
             };
-            let cell_widget = if self.cursor_pos == i {
-                Paragraph::new(Line::from(vec![v_line.bg(Color::DarkGray)]))
-                    .block(Block::bordered().merge_borders(MergeStrategy::Exact))
-            } else {
-                Paragraph::new(Line::from(vec![v_line]))
-                    .block(Block::bordered().merge_borders(MergeStrategy::Exact))
+            let pitch = cell_height as isize - 1;
+            let y = match vr {
+                -1 => grid_area.y as isize - 1,
+                9 => grid_area.y as isize + 9 * pitch + 1,
+                _ => grid_area.y as isize + pitch * vr + pitch / 2,
             };
-            cell_widget.render(cell, buf);
+            if x < 0 || y < 0 {
+                continue;
+            }
+            let slot_area = Rect::new(x as u16, y as u16, width, 1).intersection(buf.area);
+            Paragraph::new(text.yellow().bold())
+                .alignment(alignment)
+                .render(slot_area, buf);
         }
     }
-}
 
-impl SudokuWidget {
     /**
-     * Set the value on the current cursor position.
+     * Return the lines drawn inside a cell: its value, or else its notes.
+     *
+     * # Arguments
+     * * `i` the index of the cell
+     * * `tall` whether the cell has room for three lines
+     * * `highlighted` the digit whose note stands out, if any
+     */
+    fn cell_lines(&self, i: usize, tall: bool, highlighted: Option<u8>) -> Vec<Line<'static>> {
+        let (r, c) = (i / 9, i % 9);
+        let v = self.game.value(r, c);
+        let cell_notes = self.game.notes(r, c);
+        if v == 0 && cell_notes != 0 {
+            let note_span = |d: Option<u8>| -> Span<'static> {
+                match d {
+                    Some(d) if highlighted == Some(d) => {
+                        ((b'0' + d) as char).to_string().yellow().bold()
+                    }
+                    Some(d) => ((b'0' + d) as char).to_string().cyan(),
+                    None => " ".into(),
+                }
+            };
+            let notes: Vec<u8> = (1..=9).filter(|d| cell_notes & (1 << d) != 0).collect();
+            if tall {
+                return (0..3)
+                    .map(|r| {
+                        let spans: Vec<Span> = (1..=3)
+                            .map(|c| Some(3 * r + c).filter(|d| notes.contains(d)))
+                            .map(note_span)
+                            .collect();
+                        Line::from(spans)
+                    })
+                    .collect();
+            }
+            // Without room for the mini-grid, show as many notes as fit.
+            let spans: Vec<Span> = match notes[..] {
+                [a] => vec![" ".into(), note_span(Some(a)), " ".into()],
+                [a, b] => vec![note_span(Some(a)), note_span(Some(b)), " ".into()],
+                [a, b, c] => vec![note_span(Some(a)), note_span(Some(b)), note_span(Some(c))],
+                _ => vec![
+                    note_span(Some(notes[0])),
+                    note_span(Some(notes[1])),
+                    "+".cyan(),
+                ],
+            };
+            return vec![Line::from(spans)];
+        }
+        let v_text = if v == 0 {
+            "   ".to_string()
+        } else {
+            format!(" {} ", v)
+        };
+        let flag_wrong = self.show_mistakes
+            || self
+                .checked_until
+                .is_some_and(|until| Instant::now() < until);
+        let v_line = if self.game.is_given(r, c) {
+            Line::from(v_text.bold())
+        } else if flag_wrong && self.game.is_wrong(r, c) {
+            Line::from(v_text.red().bold())
+        } else {
+            Line::from(v_text)
+        };
+        if tall {
+            vec![Line::from("   "), v_line, Line::from("   ")]
+        } else {
+            vec![v_line]
+        }
+    }
+
+    pub fn solve_matrix(&mut self) {
+        // A puzzle without solution is left as is.
+        let _ = self.game.solve();
+    }
+
+    /**
+     * Set the value on the current cursor position, or clear it for `0`.
      */
     pub fn fill_value(&mut self, value: u32) {
-        if self.is_original_matrix[self.cursor_pos] {
-            return;
+        let (r, c) = (self.cursor_pos / 9, self.cursor_pos % 9);
+        // Moves breaking the rules, such as on a given, are ignored.
+        let _ = match value {
+            0 => self.game.clear(r, c),
+            v => self.game.place(r, c, v as u8),
+        };
+    }
+
+    /**
+     * Add or remove a note on the current cursor position, or clear its notes for `0`.
+     */
+    pub fn toggle_note(&mut self, value: u32) {
+        let (r, c) = (self.cursor_pos / 9, self.cursor_pos % 9);
+        let _ = match value {
+            0 => self.game.clear_notes(r, c),
+            v => self.game.toggle_note(r, c, v as u8),
+        };
+    }
+
+    /**
+     * Revert the last move, and move the cursor to the cell it changed.
+     */
+    pub fn undo(&mut self) {
+        if let Some((r, c)) = self.game.undo() {
+            self.cursor_pos = r * 9 + c;
         }
-        let r = self.cursor_pos / 9;
-        let c = self.cursor_pos % 9;
-        self.matrix.set_value(r, c, value.try_into().unwrap());
     }
 
     /**
-     * Reset the matrix to the original state./
+     * Apply the last undone move again, and move the cursor to the cell it changed.
      */
-    pub fn reset_matrix(&mut self) {
-        for i in 0..81 {
-            let r = i / 9;
-            let c = i % 9;
-            if !self.is_original_matrix[i] {
-                self.matrix.set_value(r, c, 0);
-            }
+    pub fn redo(&mut self) {
+        if let Some((r, c)) = self.game.redo() {
+            self.cursor_pos = r * 9 + c;
         }
     }
 
+    pub fn toggle_show_mistakes(&mut self) {
+        self.show_mistakes = !self.show_mistakes;
+    }
+
+    /**
+     * Flag the wrong digits for a moment, without revealing the right ones.
+     *
+     * # Returns
+     * The number of wrong digits.
+     */
+    pub fn check_board(&mut self) -> usize {
+        self.checked_until = Some(Instant::now() + CHECK_DURATION);
+        self.game.wrong_cells().len()
+    }
+
+    /**
+     * Place the right digit on the current cursor position.
+     */
+    pub fn reveal_value(&mut self) {
+        let (r, c) = (self.cursor_pos / 9, self.cursor_pos % 9);
+        // Givens and puzzles without solution are left as is.
+        let _ = self.game.reveal(r, c);
+    }
+
+    /**
+     * Return the hint found for the current board, if any.
+     */
+    fn current_hint(&self) -> Option<&Hint> {
+        self.hint
+            .as_deref()
+            .filter(|(board, _)| board == self.game.board())
+            .map(|(_, hint)| hint)
+    }
+
+    /**
+     * Find the next logical step, to be shown in the hint panel. The hint still shown is
+     * kept rather than counted as another assist.
+     *
+     * # Returns
+     * Whether a hint is shown.
+     */
+    pub fn show_hint(&mut self) -> bool {
+        if self.current_hint().is_some() {
+            return true;
+        }
+        self.hint = self
+            .game
+            .hint()
+            .map(|hint| Box::new((self.game.board().clone(), hint)));
+        self.hint.is_some()
+    }
+
+    /**
+     * Fill the notes of every empty cell with its candidates.
+     */
+    pub fn fill_candidates(&mut self) {
+        // A lost game is left as is.
+        let _ = self.game.fill_candidates();
+    }
+
+    pub fn toggle_notes_mode(&mut self) {
+        self.notes_mode = !self.notes_mode;
+    }
+
+    /**
+     * Reset the matrix to the original state, clearing the notes.
+     */
+    pub fn reset_matrix(&mut self) {
+        // A lost game is left as is.
+        let _ = self.game.reset();
+    }
+
     pub fn move_cursor_left(&mut self) {
         if !self.cursor_pos.is_multiple_of(9) {
             self.cursor_pos -= 1;
@@ -110,45 +480,629 @@ impl SudokuWidget {
     }
 }
 
+/**
+ * Puzzle variants playable in the game.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Variant {
+    /// Classic 9x9 sudoku
+    Classic,
+    /// Five overlapping 9x9 grids sharing their corner boxes
+    Samurai,
+    /// Sums between the 1 and the 9 of every row and column are given outside the grid
+    Sandwich,
+}
+
+/**
+ * How mistakes, digits differing from the unique solution, are counted.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MistakeMode {
+    /// Mistakes are not shown
+    Off,
+    /// Mistakes are counted in the title bar
+    Count,
+    /// The game is lost at the third mistake
+    ThreeStrikes,
+}
+
+/**
+ * Interval at which the screen is redrawn without input, to keep the timer running.
+ */
+const TICK: Duration = Duration::from_millis(250);
+
+/**
+ * What a mouse click on some area of the screen does.
+ */
+#[derive(Clone, Copy, Debug)]
+enum ClickTarget {
+    /// Select a cell of the classic board, by its index in row-major order.
+    Cell(usize),
+    /// Select the cell of the Samurai board rendered under the click.
+    SamuraiBoard,
+    /// Run the command of a key, such as a button of the number pad or the instructions.
+    Key(KeyEvent),
+}
+
+/**
+ * A command of the instruction bar, run with its key or by clicking it.
+ */
+struct Button {
+    name: &'static str,
+    /// Whether the mode toggled by the command is on.
+    on: bool,
+    key: &'static str,
+    event: KeyEvent,
+}
+
+impl Button {
+    fn new(name: &'static str, key: &'static str, code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Button {
+            name,
+            on: false,
+            key,
+            event: KeyEvent::new(code, modifiers),
+        }
+    }
+}
+
+/**
+ * Width of the number pad beside the classic board, borders included.
+ */
+const NUMBER_PAD_WIDTH: u16 = 13;
+
+/**
+ * The board being played, one per variant.
+ */
+#[derive(Debug)]
+enum Board {
+    Classic(SudokuWidget),
+    Samurai(SamuraiWidget),
+}
+
+/**
+ * State of the daily puzzle being played.
+ */
+#[derive(Debug)]
+struct Daily {
+    date: CalendarDate,
+    difficulty: Difficulty,
+    completed: bool,
+    /// Set once the solver has been asked for the answer, which does not count as completing.
+    solver_used: bool,
+}
+
 #[derive(Debug)]
 pub struct App {
-    sudoku: SudokuWidget,
+    board: Board,
+    daily: Option<Daily>,
+    /// Message shown in the status line until the next key press.
+    message: Option<String>,
+    mistake_mode: MistakeMode,
+    highlights: Highlights,
+    show_mistakes: bool,
+    /// Set after a first solve command, which must be repeated to solve the board.
+    confirm_solve: bool,
+    /// The new-game menu, shown instead of the board while open.
+    menu: Option<NewGameMenu>,
+    /// Whether the game was paused by opening the menu, to resume it if the menu is closed.
+    resume_after_menu: bool,
+    /// Whether the board is an empty stand-in behind the start menu, with no game yet.
+    placeholder: bool,
+    /// File of the game saved with the save command or on quit, if there is a data directory.
+    saved_game_path: Option<PathBuf>,
+    /// Areas of the last drawn screen that respond to mouse clicks.
+    click_targets: Vec<(Rect, ClickTarget)>,
     exit: bool,
 }
 
 impl App {
-    pub fn new(filled: usize) -> Self {
-        let mat = create_matrix(filled);
-        let mut is_original_matrix = [false; 81];
-        for (i, mut_ref) in is_original_matrix.iter_mut().enumerate() {
-            let x = i / 9;
-            let y = i % 9;
-            if mat.get_value(x, y) != 0 {
-                *mut_ref = true;
+    pub fn new(filled: usize, variant: Variant, symmetry: Symmetry) -> Self {
+        let board = match variant {
+            Variant::Classic => {
+                let mat = if symmetry == Symmetry::None {
+                    create_matrix(filled)
+                } else {
+                    create_symmetric_matrix(filled, symmetry)
+                };
+                Board::Classic(Self::create_classic_widget(Game::new(mat)))
             }
-        }
+            Variant::Sandwich => {
+                let (mat, clues) = create_sandwich_matrix();
+                Board::Classic(Self::create_classic_widget(Game::with_clues(
+                    mat,
+                    Some(clues),
+                )))
+            }
+            Variant::Samurai => Board::Samurai(SamuraiWidget::new(create_samurai_matrix())),
+        };
+        App::from_board(board, None)
+    }
+
+    /**
+     * Create the app playing a board, with the default settings.
+     */
+    fn from_board(board: Board, daily: Option<Daily>) -> Self {
         App {
-            sudoku: SudokuWidget {
-                matrix: mat,
-                is_original_matrix,
-                cursor_pos: 0,
-            },
+            board,
+            daily,
+            message: None,
+            mistake_mode: MistakeMode::Off,
+            highlights: Highlights::default(),
+            show_mistakes: false,
+            confirm_solve: false,
+            menu: None,
+            resume_after_menu: false,
+            placeholder: false,
+            saved_game_path: storage::saved_game_path(),
+            click_targets: vec![],
             exit: false,
         }
     }
+
+    /**
+     * Create the start screen: the new-game menu, without generating a puzzle until the
+     * player chooses one.
+     */
+    pub fn with_menu() -> Self {
+        let mut app = App::with_puzzle(SudokuMatrix::new());
+        app.placeholder = true;
+        app.open_menu();
+        app
+    }
+
+    /**
+     * Create a classic game with the given puzzle.
+     */
+    pub fn with_puzzle(mat: SudokuMatrix) -> Self {
+        App::from_board(
+            Board::Classic(Self::create_classic_widget(Game::new(mat))),
+            None,
+        )
+    }
+
+    /**
+     * Create a game with the daily puzzle of the date.
+     */
+    pub fn new_daily(date: CalendarDate) -> Self {
+        let puzzle = daily_puzzle(date, None);
+        App::from_board(
+            Board::Classic(Self::create_classic_widget(Game::new(puzzle.puzzle))),
+            Some(Daily {
+                date,
+                difficulty: puzzle.rating.difficulty,
+                completed: storage::is_daily_completed(date),
+                solver_used: false,
+            }),
+        )
+    }
+
+    /**
+     * Resume a classic game saved in a file.
+     */
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let game = Game::from_save(&content)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+        let mut daily = None;
+        for line in content.lines() {
+            match line.split_once('\t') {
+                Some(("daily", date)) => {
+                    if let Ok(date) = date.parse::<CalendarDate>() {
+                        daily = Some(Daily {
+                            date,
+                            difficulty: daily_difficulty(date),
+                            completed: storage::is_daily_completed(date),
+                            solver_used: false,
+                        });
+                    }
+                }
+                Some(("daily_solver_used", used)) => {
+                    if let Some(daily) = &mut daily {
+                        daily.solver_used = used == "true";
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(App::from_board(
+            Board::Classic(Self::create_classic_widget(game)),
+            daily,
+        ))
+    }
+
+    /**
+     * Save the classic game being played to a file, with the state of the daily puzzle.
+     */
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let Board::Classic(sudoku) = &self.board else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "only classic games can be saved",
+            ));
+        };
+        let mut content = sudoku.game.to_save();
+        if let Some(daily) = &self.daily {
+            content.push_str(&format!(
+                "daily\t{}\ndaily_solver_used\t{}\n",
+                daily.date, daily.solver_used
+            ));
+        }
+        storage::write_saved_game(path, &content)
+    }
+
+    /**
+     * Run the save or load command on the saved game in the data directory, and report the
+     * outcome in the status line.
+     */
+    fn save_or_load(&mut self, load: bool) {
+        let Some(path) = self.saved_game_path.clone() else {
+            self.message = Some("No data directory to save games in".to_string());
+            return;
+        };
+        let result = if load {
+            App::load(&path).map(|app| self.replace_game(app))
+        } else {
+            self.save(&path)
+        };
+        self.message = Some(match (load, result) {
+            (false, Ok(())) => format!("Game saved to {}", path.display()),
+            (true, Ok(())) => "Saved game loaded".to_string(),
+            (_, Err(err)) => format!(
+                "Cannot {} the game: {}",
+                if load { "load" } else { "save" },
+                err
+            ),
+        });
+    }
+
+    /**
+     * Play the game of another app, keeping the settings of this one.
+     */
+    fn replace_game(&mut self, app: App) {
+        if let Board::Classic(sudoku) = &self.board {
+            // Mistakes may have been shown or hidden during the game.
+            self.show_mistakes = sudoku.show_mistakes;
+        }
+        self.board = app.board;
+        self.daily = app.daily;
+        self.placeholder = false;
+        self.set_mistake_mode(self.mistake_mode);
+        self.set_highlights(self.highlights);
+        self.set_show_mistakes(self.show_mistakes);
+    }
+
+    /**
+     * Determine whether the player has entered digits in a game that is not over yet.
+     */
+    fn is_game_in_progress(&self) -> bool {
+        match &self.board {
+            Board::Classic(sudoku) => {
+                sudoku.game.history().position() > 0
+                    && !sudoku.game.is_solved()
+                    && !sudoku.game.is_lost()
+            }
+            Board::Samurai(samurai) => samurai.has_entries() && !samurai.matrix().is_complete(),
+        }
+    }
+
+    /**
+     * Open the new-game menu, pausing the game being played.
+     */
+    fn open_menu(&mut self) {
+        self.menu = Some(NewGameMenu::new(self.is_game_in_progress()));
+        self.resume_after_menu = false;
+        if let Board::Classic(sudoku) = &mut self.board
+            && !sudoku.game.is_paused()
+        {
+            sudoku.game.pause();
+            self.resume_after_menu = true;
+        }
+    }
+
+    fn handle_menu_key_event(&mut self, key_event: KeyEvent) {
+        let Some(menu) = &mut self.menu else {
+            return;
+        };
+        match menu.handle_key_event(key_event) {
+            MenuAction::None => {}
+            MenuAction::Cancel if self.placeholder => {
+                self.menu = None;
+                self.replace_game(App::new(25, Variant::Classic, Symmetry::None));
+            }
+            MenuAction::Cancel => {
+                self.menu = None;
+                if let Board::Classic(sudoku) = &mut self.board
+                    && self.resume_after_menu
+                {
+                    sudoku.game.resume();
+                }
+            }
+            MenuAction::Start(game) => {
+                self.menu = None;
+                self.start_game(game);
+            }
+        }
+    }
+
+    /**
+     * Replace the current game with a new one chosen in the menu.
+     */
+    fn start_game(&mut self, game: NewGame) {
+        let app = match game {
+            NewGame::Random {
+                variant: Variant::Classic,
+                difficulty,
+                seed,
+            } => {
+                let puzzle = match seed {
+                    Some(seed) => create_rated_matrix(difficulty, &mut StdRng::seed_from_u64(seed)),
+                    None => create_rated_matrix(difficulty, &mut rand::rng()),
+                };
+                App::with_puzzle(puzzle.puzzle)
+            }
+            NewGame::Random { variant, .. } => App::new(25, variant, Symmetry::None),
+            NewGame::Daily => App::new_daily(CalendarDate::today()),
+            NewGame::Import(puzzle) => App::with_puzzle(puzzle),
+        };
+        self.replace_game(app);
+    }
+
+    /**
+     * Save a classic game in progress on quit, to be resumed with `--resume`.
+     *
+     * A game without moves, or already over, is not saved, so that it does not replace
+     * the game saved before.
+     */
+    fn autosave(&self) {
+        if !matches!(self.board, Board::Classic(_)) || !self.is_game_in_progress() {
+            return;
+        }
+        if let Some(path) = &self.saved_game_path {
+            // There is no way left to report the error once quitting.
+            let _ = self.save(path);
+        }
+    }
+
+    fn create_classic_widget(game: Game) -> SudokuWidget {
+        SudokuWidget {
+            game,
+            cursor_pos: 0,
+            notes_mode: false,
+            highlights: Highlights::default(),
+            show_mistakes: false,
+            checked_until: None,
+            hint: None,
+        }
+    }
+
+    /**
+     * Set how mistakes are counted in a classic game. A game loaded with a mistake limit
+     * keeps it.
+     */
+    pub fn set_mistake_mode(&mut self, mode: MistakeMode) {
+        self.mistake_mode = mode;
+        if let Board::Classic(sudoku) = &mut self.board
+            && mode == MistakeMode::ThreeStrikes
+        {
+            sudoku.game.set_mistake_limit(Some(3));
+        }
+    }
+
+    /**
+     * Set the highlights of classic boards.
+     */
+    pub fn set_highlights(&mut self, highlights: Highlights) {
+        self.highlights = highlights;
+        if let Board::Classic(sudoku) = &mut self.board {
+            sudoku.highlights = highlights;
+        }
+    }
+
+    /**
+     * Set whether the wrong digits of classic boards are always colored.
+     */
+    pub fn set_show_mistakes(&mut self, show: bool) {
+        self.show_mistakes = show;
+        if let Board::Classic(sudoku) = &mut self.board {
+            sudoku.show_mistakes = show;
+        }
+    }
+
+    /**
+     * Flag the wrong digits of a classic board, and report how many there are.
+     */
+    fn check_board(&mut self) {
+        let Board::Classic(sudoku) = &mut self.board else {
+            return;
+        };
+        if !sudoku.game.has_unique_solution() {
+            self.message = Some("The puzzle has no unique solution to check against".to_string());
+            return;
+        }
+        self.message = Some(match sudoku.check_board() {
+            0 => "No wrong digits".to_string(),
+            1 => "1 wrong digit".to_string(),
+            n => format!("{} wrong digits", n),
+        });
+    }
+
+    /**
+     * Show the next logical step of a classic board in the hint panel.
+     */
+    fn show_hint(&mut self) {
+        if let Board::Classic(sudoku) = &mut self.board
+            && !sudoku.game.board().is_complete()
+            && !sudoku.show_hint()
+        {
+            self.message = Some("No hint: the board has a wrong digit or no solution".to_string());
+        }
+    }
+
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
             terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
+            if event::poll(TICK)? {
+                self.handle_events()?;
+            }
         }
         Ok(())
     }
 
+    /**
+     * Determine whether the classic game is paused by the player, rather than stopped
+     * because it is over.
+     */
+    fn is_paused(&self) -> bool {
+        match &self.board {
+            Board::Classic(sudoku) => {
+                sudoku.game.is_paused() && !sudoku.game.is_solved() && !sudoku.game.is_lost()
+            }
+            Board::Samurai(_) => false,
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        let paused = self.is_paused();
+        if let Board::Classic(sudoku) = &mut self.board {
+            if paused {
+                sudoku.game.resume();
+            } else {
+                sudoku.game.pause();
+            }
+        }
+    }
+
     fn draw(&mut self, frame: &mut Frame) {
         let (title_area, main_area, bottom_area) = self.calculate_main_layout(frame.area());
-        Self::render_title(frame, title_area);
+        self.click_targets.clear();
+        self.render_title(frame, title_area);
+        if let Some(menu) = &self.menu {
+            frame.render_widget(menu, main_area);
+            return;
+        }
         self.render_instruction(frame, bottom_area);
-        frame.render_widget(&self.sudoku, main_area);
+        if self.is_paused() {
+            // Hide the board, so that pausing does not give free thinking time.
+            let paused = Paragraph::new(vec![
+                Line::from("Paused".bold()),
+                Line::from(vec![
+                    "Press ".into(),
+                    "<P>".blue().bold(),
+                    " to resume".into(),
+                ]),
+            ])
+            .alignment(Alignment::Center);
+            frame.render_widget(paused, main_area.centered_vertically(Constraint::Length(2)));
+            return;
+        }
+        match &self.board {
+            Board::Classic(sudoku) => {
+                let hint = sudoku.current_hint();
+                let panel_width = if hint.is_some() { 34 } else { 0 };
+                let [grid_area, pad_area, panel_area] = Layout::horizontal([
+                    Constraint::Min(45),
+                    Constraint::Length(NUMBER_PAD_WIDTH),
+                    Constraint::Length(panel_width),
+                ])
+                .areas(main_area);
+                frame.render_widget(sudoku, grid_area);
+                for (i, cell) in sudoku.cell_areas(grid_area).into_iter().enumerate() {
+                    self.click_targets.push((cell, ClickTarget::Cell(i)));
+                }
+                let pad_targets = Self::render_number_pad(frame, pad_area, sudoku.notes_mode);
+                self.click_targets.extend(pad_targets);
+                if let Some(hint) = hint {
+                    Self::render_hint(frame, panel_area, hint);
+                }
+            }
+            Board::Samurai(samurai) => {
+                frame.render_widget(samurai, main_area);
+                self.click_targets
+                    .push((main_area, ClickTarget::SamuraiBoard));
+            }
+        }
+    }
+
+    /**
+     * Render the clickable number pad of the classic board, whose digits enter values or
+     * notes like the digit keys.
+     *
+     * # Returns
+     * The areas of the buttons with their commands.
+     */
+    fn render_number_pad(
+        frame: &mut Frame,
+        area: Rect,
+        notes_mode: bool,
+    ) -> Vec<(Rect, ClickTarget)> {
+        let pad_area = area
+            .centered(Constraint::Length(NUMBER_PAD_WIDTH), Constraint::Length(7))
+            .intersection(frame.area());
+        let inner = pad_area.inner(Margin::new(1, 1));
+        let key = |ch: char| ClickTarget::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+        let mut targets = vec![];
+        let mut lines = vec![];
+        for row in 0..3u16 {
+            let mut spans = vec![];
+            for col in 0..3u16 {
+                let digit = (b'1' + (row * 3 + col) as u8) as char;
+                let text = format!(" {} ", digit);
+                spans.push(if notes_mode {
+                    text.cyan().bold()
+                } else {
+                    text.bold()
+                });
+                if col < 2 {
+                    spans.push(" ".into());
+                }
+                let button = Rect::new(inner.x + 4 * col, inner.y + row, 3, 1);
+                targets.push((button.intersection(inner), key(digit)));
+            }
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::from("Erase").centered());
+        targets.push((
+            Rect::new(inner.x, inner.y + 3, inner.width, 1).intersection(inner),
+            key('0'),
+        ));
+        lines.push(
+            if notes_mode {
+                Line::from("Notes: on".yellow().bold())
+            } else {
+                Line::from("Notes")
+            }
+            .centered(),
+        );
+        targets.push((
+            Rect::new(inner.x, inner.y + 4, inner.width, 1).intersection(inner),
+            key('n'),
+        ));
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered()), pad_area);
+        targets
+    }
+
+    /**
+     * Render the side panel explaining a hint, with 1-based row and column numbers.
+     */
+    fn render_hint(frame: &mut Frame, area: Rect, hint: &Hint) {
+        let mut lines = vec![];
+        match hint {
+            Hint::Logic(steps) => {
+                for (i, step) in steps.iter().enumerate() {
+                    lines.push(Line::from(format!("{}. {}", i + 1, step.technique).bold()));
+                    lines.push(Line::from(step.explanation.clone()));
+                }
+            }
+            Hint::Reveal { row, col, value } => {
+                lines.push(Line::from("No known technique applies.".bold()));
+                lines.push(Line::from(format!("r{}c{} is {}", row + 1, col + 1, value)));
+            }
+        }
+        let panel = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title(" Hint "));
+        frame.render_widget(panel, area);
     }
 
     fn handle_events(&mut self) -> io::Result<()> {
@@ -156,47 +1110,171 @@ impl App {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_key_event(key_event)
             }
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event),
             _ => {}
         }
         Ok(())
     }
 
+    /**
+     * Run the command of the area clicked with the left button.
+     */
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        if mouse_event.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        let Some(&(area, target)) = self
+            .click_targets
+            .iter()
+            .find(|(area, _)| area.contains(position))
+        else {
+            return;
+        };
+        match (target, &mut self.board) {
+            (ClickTarget::Cell(i), Board::Classic(sudoku)) => sudoku.select_cell(i),
+            (ClickTarget::SamuraiBoard, Board::Samurai(samurai)) => {
+                samurai.select_at(area, position.x, position.y)
+            }
+            (ClickTarget::Key(key_event), _) => self.handle_key_event(key_event),
+            _ => {}
+        }
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        self.message = None;
+        if self.menu.is_some() {
+            return self.handle_menu_key_event(key_event);
+        }
+        let confirm_solve = std::mem::take(&mut self.confirm_solve);
+        if key_event.code == KeyCode::Char('q') && key_event.modifiers.is_empty() {
+            self.autosave();
+            self.exit();
+            return;
+        }
+        if key_event.code == KeyCode::Char('p') && key_event.modifiers.is_empty() {
+            self.toggle_pause();
+            return;
+        }
+        if self.is_paused() {
+            return;
+        }
+        if key_event.modifiers.contains(KeyModifiers::CONTROL) {
+            match key_event.code {
+                KeyCode::Char('s') => return self.save_or_load(false),
+                KeyCode::Char('o') => return self.save_or_load(true),
+                KeyCode::Char('n') => return self.open_menu(),
+                _ => {}
+            }
+        }
+        if key_event.modifiers.is_empty() {
+            match key_event.code {
+                KeyCode::Char('c') => return self.check_board(),
+                KeyCode::Char('h') => return self.show_hint(),
+                _ => {}
+            }
+        }
+        // Solving the whole board ends the game, so it is only done when asked twice.
+        let is_solve = key_event.modifiers.contains(KeyModifiers::CONTROL)
+            && key_event.code == KeyCode::Char('a');
+        if is_solve && !confirm_solve {
+            self.confirm_solve = true;
+            self.message = Some("Press <C-A> again to solve the whole board".to_string());
+            return;
+        }
+        match &mut self.board {
+            Board::Classic(sudoku) => Self::handle_classic_key_event(sudoku, key_event),
+            Board::Samurai(samurai) => Self::handle_samurai_key_event(samurai, key_event),
+        }
+        self.update_daily(key_event);
+    }
+
+    /**
+     * Record the daily puzzle as completed once the player solves it by hand.
+     */
+    fn update_daily(&mut self, key_event: KeyEvent) {
+        let Some(daily) = &mut self.daily else {
+            return;
+        };
+        if key_event.modifiers.contains(KeyModifiers::CONTROL)
+            && key_event.code == KeyCode::Char('a')
+        {
+            daily.solver_used = true;
+        }
+        let Board::Classic(sudoku) = &self.board else {
+            return;
+        };
+        if daily.completed || daily.solver_used || !sudoku.game.is_solved() {
+            return;
+        }
+        daily.completed = true;
+        // Failing to save only means the completion is forgotten next time.
+        let _ = storage::record_daily_completed(daily.date);
+    }
+
+    fn handle_classic_key_event(sudoku: &mut SudokuWidget, key_event: KeyEvent) {
         if key_event.modifiers.contains(KeyModifiers::CONTROL) {
             match key_event.code {
-                KeyCode::Char('r') => self.sudoku.reset_matrix(),
-                KeyCode::Char('a') => self.solve_matrix(),
+                KeyCode::Char('r') => sudoku.reset_matrix(),
+                KeyCode::Char('a') => sudoku.solve_matrix(),
+                KeyCode::Char('y') => sudoku.redo(),
                 _ => {}
             }
             return;
         }
         match key_event.code {
-            KeyCode::Char('q') => self.exit(),
+            KeyCode::Char('u') => sudoku.undo(),
             KeyCode::Char(c) if c.is_ascii_digit() => {
-                self.sudoku.fill_value(c.to_digit(10).unwrap());
+                let value = c.to_digit(10).unwrap();
+                if sudoku.notes_mode {
+                    sudoku.toggle_note(value);
+                } else {
+                    sudoku.fill_value(value);
+                }
             }
+            KeyCode::Char('n') => sudoku.toggle_notes_mode(),
+            KeyCode::Char('m') => sudoku.toggle_show_mistakes(),
+            KeyCode::Char('r') => sudoku.reveal_value(),
+            KeyCode::Char('f') => sudoku.fill_candidates(),
             KeyCode::Left => {
-                self.sudoku.move_cursor_left();
+                sudoku.move_cursor_left();
             }
             KeyCode::Right => {
-                self.sudoku.move_cursor_right();
+                sudoku.move_cursor_right();
             }
             KeyCode::Up => {
-                self.sudoku.move_curosor_up();
+                sudoku.move_curosor_up();
             }
             KeyCode::Down => {
-                self.sudoku.move_curosor_down();
+                sudoku.move_curosor_down();
             }
             _ => {}
         }
     }
 
-    fn exit(&mut self) {
-        self.exit = true;
+    fn handle_samurai_key_event(samurai: &mut SamuraiWidget, key_event: KeyEvent) {
+        if key_event.modifiers.contains(KeyModifiers::CONTROL) {
+            match key_event.code {
+                KeyCode::Char('r') => samurai.reset_matrix(),
+                KeyCode::Char('a') => samurai.solve_matrix(),
+                _ => {}
+            }
+            return;
+        }
+        match key_event.code {
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                samurai.fill_value(c.to_digit(10).unwrap());
+            }
+            KeyCode::Left => samurai.move_cursor(0, -1),
+            KeyCode::Right => samurai.move_cursor(0, 1),
+            KeyCode::Up => samurai.move_cursor(-1, 0),
+            KeyCode::Down => samurai.move_cursor(1, 0),
+            _ => {}
+        }
     }
 
-    fn solve_matrix(&mut self) {
-        solve_sudoku(&mut self.sudoku.matrix, false);
+    fn exit(&mut self) {
+        self.exit = true;
     }
 
     // Split the layout into a top row, middle space and a bottom row
@@ -204,39 +1282,162 @@ impl App {
         let main_layout = Layout::vertical([
             Constraint::Length(1),
             Constraint::Min(19),
-            Constraint::Length(2),
+            Constraint::Length(3),
         ]);
         let [top_row, main, bottom_row] = main_layout.areas(area);
         (top_row, main, bottom_row)
     }
 
-    fn render_title(frame: &mut Frame, area: Rect) {
+    fn render_title(&self, frame: &mut Frame, area: Rect) {
+        let mut title = vec![" Sudoku ".bold()];
+        if let Some(daily) = &self.daily {
+            title.push(format!("· Daily {} ({}) ", daily.date, daily.difficulty).into());
+            if daily.completed {
+                title.push(" ✓ Completed ".green().bold());
+            }
+        }
+        if let Board::Classic(sudoku) = &self.board {
+            let secs = sudoku.game.elapsed().as_secs();
+            let time = if secs >= 3600 {
+                format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            } else {
+                format!("{:02}:{:02}", secs / 60, secs % 60)
+            };
+            title.push(format!("· {} ", time).into());
+            let mistakes = sudoku.game.mistakes();
+            match sudoku.game.mistake_limit() {
+                Some(limit) => title.push(format!("· Mistakes {}/{} ", mistakes, limit).into()),
+                None if self.mistake_mode != MistakeMode::Off => {
+                    title.push(format!("· Mistakes {} ", mistakes).into())
+                }
+                None => {}
+            }
+            let assists = sudoku.game.assists();
+            if assists > 0 {
+                title.push(format!("· Assists {} ", assists).into());
+            }
+        }
         frame.render_widget(
-            Paragraph::new(" Sudoku ".bold()).alignment(Alignment::Center),
+            Paragraph::new(Line::from(title)).alignment(Alignment::Center),
             area,
         );
     }
 
-    fn render_instruction(&self, frame: &mut Frame, area: Rect) {
-        let status_label = self.get_status_text_line();
-        let instructions = Line::from(vec![
-            "Reset ".into(),
-            "<C-R>".blue().bold(),
-            " Solve ".into(),
-            "<C-A>".blue().bold(),
-            " Quit ".into(),
-            "<Q>".blue().bold(),
+    /**
+     * Return the commands of the instruction bar, one list per line.
+     */
+    fn instruction_buttons(&self) -> Vec<Vec<Button>> {
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let mut lines = vec![];
+        let mut commands = vec![];
+        if let Board::Classic(sudoku) = &self.board {
+            lines.push(vec![
+                Button {
+                    on: sudoku.notes_mode,
+                    ..Button::new("Notes", "<N>", KeyCode::Char('n'), none)
+                },
+                Button {
+                    on: sudoku.show_mistakes,
+                    ..Button::new("Mistakes", "<M>", KeyCode::Char('m'), none)
+                },
+                Button::new("Undo", "<U>", KeyCode::Char('u'), none),
+                Button::new("Redo", "<C-Y>", KeyCode::Char('y'), ctrl),
+                Button::new("Check", "<C>", KeyCode::Char('c'), none),
+                Button::new("Hint", "<H>", KeyCode::Char('h'), none),
+                Button::new("Reveal", "<R>", KeyCode::Char('r'), none),
+                Button::new("Candidates", "<F>", KeyCode::Char('f'), none),
+            ]);
+            commands.extend([
+                Button::new("Pause", "<P>", KeyCode::Char('p'), none),
+                Button::new("Save", "<C-S>", KeyCode::Char('s'), ctrl),
+                Button::new("Load", "<C-O>", KeyCode::Char('o'), ctrl),
+            ]);
+        }
+        commands.extend([
+            Button::new("Reset", "<C-R>", KeyCode::Char('r'), ctrl),
+            Button::new("Solve", "<C-A>", KeyCode::Char('a'), ctrl),
+            Button::new("New", "<C-N>", KeyCode::Char('n'), ctrl),
+            Button::new("Quit", "<Q>", KeyCode::Char('q'), none),
         ]);
+        lines.push(commands);
+        lines
+    }
+
+    /**
+     * Render the status line and the instruction bar, whose commands can be clicked.
+     */
+    fn render_instruction(&mut self, frame: &mut Frame, area: Rect) {
+        let [status_area, instruction_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
         frame.render_widget(
-            Paragraph::new(vec![status_label, instructions]).alignment(Alignment::Center),
-            area,
+            Paragraph::new(self.get_status_text_line()).alignment(Alignment::Center),
+            status_area,
         );
+        for (buttons, y) in self
+            .instruction_buttons()
+            .into_iter()
+            .zip(instruction_area.rows().map(|row| row.y))
+        {
+            let mut spans = vec![];
+            let mut widths = vec![];
+            for (i, button) in buttons.iter().enumerate() {
+                let name = match (i, button.on) {
+                    (0, false) => format!("{} ", button.name),
+                    (0, true) => format!("{}: on ", button.name),
+                    (_, false) => format!(" {} ", button.name),
+                    (_, true) => format!(" {}: on ", button.name),
+                };
+                widths.push((name.chars().count() + button.key.len()) as u16);
+                spans.push(if button.on {
+                    name.yellow().bold()
+                } else {
+                    name.into()
+                });
+                spans.push(button.key.blue().bold());
+            }
+            // Center the line by hand, to know where each command is.
+            let line = Line::from(spans);
+            let line_width = line.width() as u16;
+            let mut x = area.x + area.width.saturating_sub(line_width) / 2;
+            let line_area = Rect::new(x, y, line_width, 1).intersection(area);
+            frame.render_widget(line, line_area);
+            for (button, width) in buttons.iter().zip(widths) {
+                let button_area = Rect::new(x, y, width, 1).intersection(area);
+                self.click_targets
+                    .push((button_area, ClickTarget::Key(button.event)));
+                x += width;
+            }
+        }
     }
 
     fn get_status_text_line(&self) -> Line<'_> {
-        if !self.sudoku.matrix.is_compatible() {
+        if let Some(message) = &self.message {
+            return Line::from(message.as_str().yellow());
+        }
+        if let Board::Classic(sudoku) = &self.board
+            && sudoku.game.is_lost()
+        {
+            return Line::from(vec![
+                format!("Game over: {} mistakes", sudoku.game.mistakes())
+                    .white()
+                    .bold()
+                    .bg(Color::Red),
+            ]);
+        }
+        let (is_compatible, is_complete) = match &self.board {
+            Board::Classic(sudoku) => (
+                sudoku.game.is_compatible(),
+                sudoku.game.board().is_complete(),
+            ),
+            Board::Samurai(samurai) => (
+                samurai.matrix().is_compatible(),
+                samurai.matrix().is_complete(),
+            ),
+        };
+        if !is_compatible {
             Line::from(vec!["CONFLICT!".white().bold().bg(Color::Red)])
-        } else if self.sudoku.matrix.is_complete() {
+        } else if is_complete {
             Line::from(vec![
                 "🎉 Congratulations! Game solved! 🎉"
                     .white()
@@ -251,6 +1452,118 @@ impl App {
 
 impl Default for App {
     fn default() -> Self {
-        Self::new(25)
+        Self::new(25, Variant::Classic, Symmetry::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    /**
+     * Draw the app on an 80x25 screen, so that its click targets are laid out.
+     *
+     * The main area spans lines 1 to 21. The classic grid, with compact cells, is centered
+     * in its left 67 columns at (15, 2), and the number pad in the 13 columns after it,
+     * with its buttons from (68, 9).
+     */
+    fn draw(app: &mut App) {
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+    }
+
+    fn click(app: &mut App, x: u16, y: u16) {
+        app.handle_mouse_event(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: x,
+            row: y,
+            modifiers: KeyModifiers::NONE,
+        });
+    }
+
+    fn classic(app: &App) -> &SudokuWidget {
+        match &app.board {
+            Board::Classic(sudoku) => sudoku,
+            Board::Samurai(_) => panic!("not a classic board"),
+        }
+    }
+
+    #[test]
+    fn test_click_classic_cells() {
+        let mut app = App::with_puzzle(SudokuMatrix::new());
+        draw(&mut app);
+        // Cells are 5 columns by 3 lines, sharing their borders with their neighbors.
+        for (x, y, cell) in [
+            (17, 3, 0),
+            (15, 2, 0),
+            (49, 3, 8),
+            (17, 19, 72),
+            (51, 20, 80),
+            (37, 11, 41),
+            // A shared border belongs to the cell above or on the left.
+            (19, 3, 0),
+            (20, 3, 1),
+            (17, 4, 0),
+            (17, 5, 9),
+        ] {
+            click(&mut app, x, y);
+            assert_eq!(classic(&app).cursor_pos, cell, "click at ({}, {})", x, y);
+        }
+        // Clicks around the grid do not move the cursor.
+        for (x, y) in [(14, 3), (52, 3), (17, 1), (17, 21)] {
+            click(&mut app, x, y);
+            assert_eq!(classic(&app).cursor_pos, 9, "click at ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn test_click_number_pad() {
+        let mut app = App::with_puzzle(SudokuMatrix::new());
+        draw(&mut app);
+        click(&mut app, 37, 11);
+        // Buttons are 3 columns wide with a blank column between them.
+        click(&mut app, 73, 10);
+        assert_eq!(classic(&app).game.value(4, 5), 5);
+        click(&mut app, 71, 10);
+        click(&mut app, 79, 9);
+        assert_eq!(classic(&app).game.value(4, 5), 5);
+        click(&mut app, 78, 11);
+        assert_eq!(classic(&app).game.value(4, 5), 9);
+        click(&mut app, 68, 9);
+        assert_eq!(classic(&app).game.value(4, 5), 1);
+        // Erase.
+        click(&mut app, 74, 12);
+        assert_eq!(classic(&app).game.value(4, 5), 0);
+        // Notes mode, then the digits enter notes.
+        click(&mut app, 68, 13);
+        assert!(classic(&app).notes_mode);
+        draw(&mut app);
+        click(&mut app, 69, 9);
+        assert_eq!(classic(&app).game.value(4, 5), 0);
+        assert_eq!(classic(&app).game.notes(4, 5), 1 << 1);
+    }
+
+    #[test]
+    fn test_quit_saves_only_games_in_progress() {
+        let path =
+            std::env::temp_dir().join(format!("sudoku-tui-autosave-{}.txt", std::process::id()));
+        fs::write(&path, "previous save").unwrap();
+        let key = |ch: char| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+
+        // Looking at a game and quitting keeps the previous save.
+        let mut app = App::with_puzzle(SudokuMatrix::new());
+        app.saved_game_path = Some(path.clone());
+        app.handle_key_event(key('q'));
+        assert!(app.exit);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "previous save");
+
+        let mut app = App::with_puzzle(SudokuMatrix::new());
+        app.saved_game_path = Some(path.clone());
+        app.handle_key_event(key('5'));
+        app.handle_key_event(key('q'));
+        let loaded = App::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(classic(&loaded.unwrap()).game.value(0, 0), 5);
     }
 }
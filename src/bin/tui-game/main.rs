@@ -1,19 +1,140 @@
 mod app;
+mod menu;
+mod samurai;
+mod storage;
 
-use app::App;
+use app::{App, Highlight, Highlights, MistakeMode, Variant};
 use clap::Parser;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use std::io;
+use std::path::{Path, PathBuf};
+use sudoku_lib::{
+    BankQuery, CalendarDate, Difficulty, PuzzleBank, SudokuMatrix, Symmetry, create_rated_matrix,
+};
 
 #[derive(Parser)]
 #[command(name = "tui-game")]
 #[command(about = "A TUI Sudoku game")]
 struct Cli {
-    /// Number of cells to be filled in the sudoku matrix
-    #[arg(short, long, default_value_t = 25)]
-    filled: usize,
+    /// Number of cells to be filled in the sudoku matrix [default: 25]
+    #[arg(short, long)]
+    filled: Option<usize>,
+
+    /// Puzzle variant to play. `--filled` only applies to the classic variant.
+    #[arg(long, value_enum)]
+    variant: Option<Variant>,
+
+    /// Symmetry of the clue layout of a classic puzzle. Any symmetry other than `none`
+    /// also guarantees a unique solution, with `--filled` as the minimum clue count.
+    #[arg(long)]
+    symmetry: Option<Symmetry>,
+
+    /// Play a classic puzzle of this difficulty instead of `--filled` clues
+    #[arg(short, long)]
+    difficulty: Option<Difficulty>,
+
+    /// Serve a classic puzzle from this puzzle bank file, optionally matching
+    /// `--difficulty`. Puzzles are never served twice; a new one is generated when the
+    /// bank runs out.
+    #[arg(long)]
+    bank: Option<PathBuf>,
+
+    /// Play the puzzle of the day, the same for everyone. Other puzzle options are ignored.
+    #[arg(long)]
+    daily: bool,
+
+    /// How mistakes, digits differing from the unique solution, are counted in classic games
+    /// [default: off]. A resumed game keeps the mistake limit it was saved with.
+    #[arg(long, value_enum)]
+    mistakes: Option<MistakeMode>,
+
+    /// Highlights of classic games, separated by commas
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Highlight::Peers, Highlight::Digits, Highlight::Notes])]
+    highlight: Vec<Highlight>,
+
+    /// Color the digits that differ from the solution
+    #[arg(long)]
+    show_mistakes: bool,
+
+    /// Resume the game saved on quit or with the save command. Other puzzle options are
+    /// ignored; `--mistakes`, `--highlight` and `--show-mistakes` still apply.
+    #[arg(long, conflicts_with = "load")]
+    resume: bool,
+
+    /// Resume a game saved in this file. Other puzzle options are ignored; `--mistakes`,
+    /// `--highlight` and `--show-mistakes` still apply.
+    #[arg(long, value_name = "FILE")]
+    load: Option<PathBuf>,
+}
+
+/**
+ * Draw a puzzle from the bank and save the bank, so that the puzzle is not served again.
+ */
+fn draw_from_bank(path: &Path, difficulty: Option<Difficulty>) -> io::Result<Option<SudokuMatrix>> {
+    let mut bank = PuzzleBank::open(path).map_err(io::Error::other)?;
+    let query = BankQuery {
+        difficulty,
+        ..BankQuery::default()
+    };
+    let Some(entry) = bank.draw(&query, &mut rand::rng()) else {
+        return Ok(None);
+    };
+    let puzzle = entry.puzzle.clone();
+    bank.save()?;
+    Ok(Some(puzzle))
 }
 
 fn main() -> io::Result<()> {
     let cli = Cli::parse();
-    ratatui::run(|terminal| App::new(cli.filled).run(terminal))
+    // Without any puzzle option, let the player choose the game on the start screen.
+    let starts_with_menu = !cli.resume
+        && cli.load.is_none()
+        && !cli.daily
+        && cli.bank.is_none()
+        && cli.difficulty.is_none()
+        && cli.filled.is_none()
+        && cli.variant.is_none()
+        && cli.symmetry.is_none();
+    let mut app = if starts_with_menu {
+        App::with_menu()
+    } else if cli.resume {
+        let path = storage::saved_game_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        App::load(&path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("cannot resume {}: {}", path.display(), err),
+            )
+        })?
+    } else if let Some(path) = &cli.load {
+        App::load(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("cannot load {}: {}", path.display(), err),
+            )
+        })?
+    } else if cli.daily {
+        App::new_daily(CalendarDate::today())
+    } else if let Some(path) = &cli.bank
+        && let Some(puzzle) = draw_from_bank(path, cli.difficulty)?
+    {
+        App::with_puzzle(puzzle)
+    } else if let Some(difficulty) = cli.difficulty {
+        App::with_puzzle(create_rated_matrix(difficulty, &mut rand::rng()).puzzle)
+    } else {
+        App::new(
+            cli.filled.unwrap_or(25),
+            cli.variant.unwrap_or(Variant::Classic),
+            cli.symmetry.unwrap_or(Symmetry::None),
+        )
+    };
+    if let Some(mode) = cli.mistakes {
+        app.set_mistake_mode(mode);
+    }
+    app.set_highlights(Highlights::new(&cli.highlight));
+    app.set_show_mistakes(cli.show_mistakes);
+    crossterm::execute!(io::stdout(), EnableMouseCapture)?;
+    let result = ratatui::run(|terminal| app.run(terminal));
+    crossterm::execute!(io::stdout(), DisableMouseCapture)?;
+    result
 }
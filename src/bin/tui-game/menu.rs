@@ -0,0 +1,477 @@
+use crate::app::Variant;
+use crossterm::event::{KeyCode, KeyEvent};
+use sudoku_lib::{Difficulty, SudokuMatrix, count_solutions};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+
+/**
+ * Where the puzzle of a new game comes from.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuzzleSource {
+    /// A newly generated puzzle
+    Random,
+    /// The puzzle of the day, the same for everyone
+    Daily,
+    /// A puzzle typed or pasted by the player
+    Import,
+}
+
+impl PuzzleSource {
+    pub const ALL: [PuzzleSource; 3] = [
+        PuzzleSource::Random,
+        PuzzleSource::Daily,
+        PuzzleSource::Import,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PuzzleSource::Random => "Random",
+            PuzzleSource::Daily => "Puzzle of the day",
+            PuzzleSource::Import => "Import",
+        }
+    }
+}
+
+/**
+ * Rows of the menu that the player can select.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuField {
+    Source,
+    Variant,
+    Difficulty,
+    Seed,
+    Puzzle,
+}
+
+/**
+ * The game chosen in the menu.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NewGame {
+    /// A generated puzzle. The difficulty and the seed only apply to the classic variant.
+    Random {
+        variant: Variant,
+        difficulty: Difficulty,
+        seed: Option<u64>,
+    },
+    Daily,
+    Import(SudokuMatrix),
+}
+
+/**
+ * Outcome of a key press in the menu.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    /// The menu stays open.
+    None,
+    /// The menu is closed, going back to the current game.
+    Cancel,
+    /// The menu is closed to start a new game.
+    Start(NewGame),
+}
+
+/**
+ * Screen to pick the next game: a random puzzle of some variant and difficulty, the
+ * puzzle of the day, or an imported puzzle.
+ */
+#[derive(Debug)]
+pub struct NewGameMenu {
+    selected: usize,
+    source: PuzzleSource,
+    variant: Variant,
+    difficulty: Difficulty,
+    /// Seed typed by the player; a random puzzle is generated when empty.
+    seed: String,
+    /// Puzzle typed or pasted by the player, in the line format.
+    puzzle: String,
+    /// Whether starting a game abandons a game in progress, which needs a confirmation.
+    abandons_game: bool,
+    /// Game waiting for the confirmation to abandon the game in progress.
+    pending: Option<NewGame>,
+    error: Option<String>,
+}
+
+impl NewGameMenu {
+    /**
+     * Create a menu.
+     *
+     * # Arguments
+     * * `abandons_game` whether a game in progress is lost when the new game starts
+     */
+    pub fn new(abandons_game: bool) -> Self {
+        NewGameMenu {
+            selected: 0,
+            source: PuzzleSource::Random,
+            variant: Variant::Classic,
+            difficulty: Difficulty::Medium,
+            seed: String::new(),
+            puzzle: String::new(),
+            abandons_game,
+            pending: None,
+            error: None,
+        }
+    }
+
+    /**
+     * Return the rows shown for the current puzzle source.
+     */
+    fn fields(&self) -> Vec<MenuField> {
+        match self.source {
+            PuzzleSource::Random if self.variant == Variant::Classic => vec![
+                MenuField::Source,
+                MenuField::Variant,
+                MenuField::Difficulty,
+                MenuField::Seed,
+            ],
+            PuzzleSource::Random => vec![MenuField::Source, MenuField::Variant],
+            PuzzleSource::Daily => vec![MenuField::Source],
+            PuzzleSource::Import => vec![MenuField::Source, MenuField::Puzzle],
+        }
+    }
+
+    fn selected_field(&self) -> MenuField {
+        self.fields()[self.selected]
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> MenuAction {
+        if let Some(game) = self.pending.take() {
+            return match key_event.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => MenuAction::Start(game),
+                _ => MenuAction::None,
+            };
+        }
+        self.error = None;
+        match key_event.code {
+            KeyCode::Esc => return MenuAction::Cancel,
+            KeyCode::Enter => match self.chosen_game() {
+                Some(game) if self.abandons_game => self.pending = Some(game),
+                Some(game) => return MenuAction::Start(game),
+                None => {}
+            },
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(self.fields().len() - 1),
+            KeyCode::Left => self.cycle(-1),
+            KeyCode::Right => self.cycle(1),
+            KeyCode::Backspace => {
+                match self.selected_field() {
+                    MenuField::Seed => self.seed.pop(),
+                    MenuField::Puzzle => self.puzzle.pop(),
+                    _ => None,
+                };
+            }
+            KeyCode::Char(ch) => match self.selected_field() {
+                MenuField::Seed if ch.is_ascii_digit() => self.seed.push(ch),
+                MenuField::Puzzle if ch.is_ascii_digit() || ch == '.' => self.puzzle.push(ch),
+                _ => {}
+            },
+            _ => {}
+        }
+        MenuAction::None
+    }
+
+    /**
+     * Switch the option of the selected row to the next or previous one.
+     */
+    fn cycle(&mut self, step: isize) {
+        fn next<T: Copy + PartialEq>(all: &[T], current: T, step: isize) -> T {
+            let idx = all.iter().position(|v| *v == current).unwrap_or(0) as isize;
+            all[(idx + step).rem_euclid(all.len() as isize) as usize]
+        }
+        match self.selected_field() {
+            MenuField::Source => self.source = next(&PuzzleSource::ALL, self.source, step),
+            MenuField::Variant => {
+                self.variant = next(
+                    &[Variant::Classic, Variant::Sandwich, Variant::Samurai],
+                    self.variant,
+                    step,
+                )
+            }
+            MenuField::Difficulty => {
+                self.difficulty = next(&Difficulty::ALL, self.difficulty, step)
+            }
+            MenuField::Seed | MenuField::Puzzle => {}
+        }
+    }
+
+    /**
+     * Check the options and return the game to start.
+     *
+     * # Returns
+     * The game, or `None` with the error shown in the menu.
+     */
+    fn chosen_game(&mut self) -> Option<NewGame> {
+        let game = match self.source {
+            PuzzleSource::Random => {
+                let seed = if self.seed.is_empty() {
+                    None
+                } else if let Ok(seed) = self.seed.parse() {
+                    Some(seed)
+                } else {
+                    self.error = Some("The seed is too large".to_string());
+                    return None;
+                };
+                NewGame::Random {
+                    variant: self.variant,
+                    difficulty: self.difficulty,
+                    seed,
+                }
+            }
+            PuzzleSource::Daily => NewGame::Daily,
+            PuzzleSource::Import => {
+                let puzzle: SudokuMatrix = match self.puzzle.parse() {
+                    Ok(puzzle) => puzzle,
+                    Err(err) => {
+                        self.error = Some(format!("Invalid puzzle: {}", err));
+                        return None;
+                    }
+                };
+                if count_solutions(&puzzle, 1) == 0 {
+                    self.error = Some("The puzzle has no solution".to_string());
+                    return None;
+                }
+                NewGame::Import(puzzle)
+            }
+        };
+        Some(game)
+    }
+
+    fn field_line(&self, field: MenuField, selected: bool) -> Line<'static> {
+        let (label, value) = match field {
+            MenuField::Source => ("Puzzle", self.source.label().to_string()),
+            MenuField::Variant => (
+                "Variant",
+                match self.variant {
+                    Variant::Classic => "Classic",
+                    Variant::Samurai => "Samurai",
+                    Variant::Sandwich => "Sandwich",
+                }
+                .to_string(),
+            ),
+            MenuField::Difficulty => ("Difficulty", self.difficulty.to_string()),
+            MenuField::Seed => ("Seed", self.seed.clone()),
+            MenuField::Puzzle => {
+                // Only the end of a long puzzle fits, with the number of cells typed.
+                let count = self.puzzle.chars().count();
+                let tail: String = self.puzzle.chars().skip(count.saturating_sub(36)).collect();
+                let ellipsis = if count > 36 { "…" } else { "" };
+                ("Cells", format!("{:>2}/81 {}{}", count, ellipsis, tail))
+            }
+        };
+        let is_text = matches!(field, MenuField::Seed | MenuField::Puzzle);
+        let value = match (is_text, selected) {
+            (true, true) => format!("{}_", value),
+            (true, false) => value,
+            (false, true) => format!("< {} >", value),
+            (false, false) => format!("  {}  ", value),
+        };
+        let mut spans = vec![Span::from(format!("{:<12}", label)), Span::from(value)];
+        if selected {
+            spans = spans.into_iter().map(|span| span.bold()).collect();
+        }
+        let line = Line::from(spans);
+        if selected {
+            line.bg(Color::DarkGray)
+        } else {
+            line
+        }
+    }
+}
+
+impl Widget for &NewGameMenu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut lines = vec![];
+        for (i, field) in self.fields().into_iter().enumerate() {
+            lines.push(self.field_line(field, i == self.selected));
+        }
+        if self.source == PuzzleSource::Import {
+            lines.push(Line::from(""));
+            lines.push(Line::from(
+                "81 cells in row-major order, with . or 0 for empty cells".italic(),
+            ));
+        }
+        lines.push(Line::from(""));
+        if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone().red().bold()));
+        } else if self.pending.is_some() {
+            lines.push(Line::from(vec![
+                "Abandon the current game? ".yellow().bold(),
+                "<Y>".blue().bold(),
+                " Yes ".into(),
+                "<N>".blue().bold(),
+                " No".into(),
+            ]));
+        } else {
+            lines.push(Line::from(vec![
+                "<↑↓>".blue().bold(),
+                " Select ".into(),
+                "<←→>".blue().bold(),
+                " Change ".into(),
+                "<Enter>".blue().bold(),
+                " Start ".into(),
+                "<Esc>".blue().bold(),
+                " Back".into(),
+            ]));
+        }
+        let height = lines.len() as u16 + 2;
+        let menu_area = area.centered(Constraint::Length(64), Constraint::Length(height));
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" New game ".bold()))
+            .render(menu_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(menu: &mut NewGameMenu, code: KeyCode) -> MenuAction {
+        menu.handle_key_event(KeyEvent::from(code))
+    }
+
+    fn type_text(menu: &mut NewGameMenu, text: &str) {
+        for ch in text.chars() {
+            assert_eq!(press(menu, KeyCode::Char(ch)), MenuAction::None);
+        }
+    }
+
+    #[test]
+    fn test_fields_per_source_and_variant() {
+        let mut menu = NewGameMenu::new(false);
+        assert_eq!(menu.fields().len(), 4);
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Right);
+        assert_eq!(menu.variant, Variant::Sandwich);
+        assert_eq!(menu.fields(), [MenuField::Source, MenuField::Variant]);
+        press(&mut menu, KeyCode::Right);
+        assert_eq!(menu.variant, Variant::Samurai);
+        assert_eq!(menu.fields(), [MenuField::Source, MenuField::Variant]);
+        // The selection stays within the rows shown.
+        press(&mut menu, KeyCode::Down);
+        assert_eq!(menu.selected_field(), MenuField::Variant);
+
+        press(&mut menu, KeyCode::Up);
+        press(&mut menu, KeyCode::Right);
+        assert_eq!(menu.source, PuzzleSource::Daily);
+        assert_eq!(menu.fields(), [MenuField::Source]);
+        press(&mut menu, KeyCode::Right);
+        assert_eq!(menu.source, PuzzleSource::Import);
+        assert_eq!(menu.fields(), [MenuField::Source, MenuField::Puzzle]);
+        press(&mut menu, KeyCode::Left);
+        press(&mut menu, KeyCode::Left);
+        assert_eq!(menu.source, PuzzleSource::Random);
+    }
+
+    #[test]
+    fn test_start_random_game() {
+        let mut menu = NewGameMenu::new(false);
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Right);
+        press(&mut menu, KeyCode::Down);
+        // Only digits go into the seed.
+        type_text(&mut menu, "4x2");
+        assert_eq!(
+            press(&mut menu, KeyCode::Enter),
+            MenuAction::Start(NewGame::Random {
+                variant: Variant::Classic,
+                difficulty: Difficulty::Hard,
+                seed: Some(42),
+            })
+        );
+        assert_eq!(press(&mut menu, KeyCode::Esc), MenuAction::Cancel);
+    }
+
+    #[test]
+    fn test_seed_too_large() {
+        let mut menu = NewGameMenu::new(false);
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Down);
+        type_text(&mut menu, &format!("{}0", u64::MAX));
+        assert_eq!(press(&mut menu, KeyCode::Enter), MenuAction::None);
+        assert_eq!(menu.error.as_deref(), Some("The seed is too large"));
+        // The error goes away with the next key.
+        press(&mut menu, KeyCode::Backspace);
+        assert_eq!(menu.error, None);
+        assert_eq!(
+            press(&mut menu, KeyCode::Enter),
+            MenuAction::Start(NewGame::Random {
+                variant: Variant::Classic,
+                difficulty: Difficulty::Medium,
+                seed: Some(u64::MAX),
+            })
+        );
+    }
+
+    #[test]
+    fn test_import_validation() {
+        let mut menu = NewGameMenu::new(false);
+        press(&mut menu, KeyCode::Left);
+        assert_eq!(menu.source, PuzzleSource::Import);
+        press(&mut menu, KeyCode::Down);
+        type_text(&mut menu, "1234");
+        assert_eq!(press(&mut menu, KeyCode::Enter), MenuAction::None);
+        assert!(
+            menu.error
+                .as_deref()
+                .unwrap()
+                .starts_with("Invalid puzzle: "),
+            "{:?}",
+            menu.error
+        );
+
+        // The last cell of the first row can only be a 9, which its column already holds.
+        for _ in 0..4 {
+            press(&mut menu, KeyCode::Backspace);
+        }
+        type_text(&mut menu, &format!("12345678.........9{}", ".".repeat(63)));
+        assert_eq!(press(&mut menu, KeyCode::Enter), MenuAction::None);
+        assert_eq!(menu.error.as_deref(), Some("The puzzle has no solution"));
+
+        let puzzle =
+            ".9.....54.34.5.....8...19......2....51...8.7.729..5.4..5.7.4..8.......1...83.....";
+        menu.puzzle.clear();
+        type_text(&mut menu, puzzle);
+        assert_eq!(
+            press(&mut menu, KeyCode::Enter),
+            MenuAction::Start(NewGame::Import(puzzle.parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_abandon_confirmation() {
+        let mut menu = NewGameMenu::new(true);
+        press(&mut menu, KeyCode::Right);
+        assert_eq!(menu.source, PuzzleSource::Daily);
+        assert_eq!(press(&mut menu, KeyCode::Enter), MenuAction::None);
+        assert_eq!(menu.pending, Some(NewGame::Daily));
+        // Any other key than Y keeps the game in progress, and the menu open.
+        assert_eq!(press(&mut menu, KeyCode::Char('n')), MenuAction::None);
+        assert_eq!(menu.pending, None);
+        assert_eq!(press(&mut menu, KeyCode::Enter), MenuAction::None);
+        assert_eq!(press(&mut menu, KeyCode::Esc), MenuAction::None);
+        assert_eq!(menu.pending, None);
+        press(&mut menu, KeyCode::Enter);
+        assert_eq!(
+            press(&mut menu, KeyCode::Char('Y')),
+            MenuAction::Start(NewGame::Daily)
+        );
+
+        // Without a game in progress, the game starts right away.
+        let mut menu = NewGameMenu::new(false);
+        press(&mut menu, KeyCode::Right);
+        assert_eq!(
+            press(&mut menu, KeyCode::Enter),
+            MenuAction::Start(NewGame::Daily)
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use sudoku_lib::{SAMURAI_SIZE, SamuraiMatrix, solve_samurai};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+/**
+ * Widget to render a Samurai sudoku.
+ *
+ * The combined 21x21 board does not fit most terminals, so the view scrolls to keep
+ * the cursor visible.
+ */
+#[derive(Debug)]
+pub struct SamuraiWidget {
+    matrix: SamuraiMatrix,
+    is_original_matrix: Vec<bool>,
+    cursor: (usize, usize),
+}
+
+// Each cell is drawn 2 characters wide, with an extra blank column and row between boxes.
+const BOARD_WIDTH: usize = SAMURAI_SIZE * 2 + SAMURAI_SIZE / 3;
+const BOARD_HEIGHT: usize = SAMURAI_SIZE + SAMURAI_SIZE / 3;
+
+impl Widget for &SamuraiWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut lines = vec![];
+        for r in 0..SAMURAI_SIZE {
+            if r % 3 == 0 && r != 0 {
+                lines.push(Line::from(""));
+            }
+            let mut spans = vec![];
+            for c in 0..SAMURAI_SIZE {
+                if c % 3 == 0 && c != 0 {
+                    spans.push(Span::from(" "));
+                }
+                let v = self.matrix.get_value(r, c);
+                let text = if !SamuraiMatrix::is_cell_used(r, c) {
+                    "  ".to_string()
+                } else if v == 0 {
+                    " ·".to_string()
+                } else {
+                    format!(" {}", v)
+                };
+                let mut span = if self.is_original_matrix[r * SAMURAI_SIZE + c] {
+                    text.bold()
+                } else {
+                    text.cyan()
+                };
+                if self.cursor == (r, c) {
+                    span = span.bg(Color::DarkGray);
+                }
+                spans.push(span);
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let (board_area, scroll_y, scroll_x) = self.view(area);
+        Paragraph::new(lines)
+            .scroll((scroll_y as u16, scroll_x as u16))
+            .render(board_area, buf);
+    }
+}
+
+impl SamuraiWidget {
+    /**
+     * Compute the visible part of the board when rendered in an area.
+     *
+     * # Returns
+     * The area of the board on screen, and the scroll offsets in lines and columns.
+     */
+    fn view(&self, area: Rect) -> (Rect, usize, usize) {
+        let width = BOARD_WIDTH.min(area.width as usize);
+        let height = BOARD_HEIGHT.min(area.height as usize);
+        let cursor_line = self.cursor.0 + self.cursor.0 / 3;
+        let cursor_col = self.cursor.1 * 2 + self.cursor.1 / 3;
+        let scroll_y = cursor_line
+            .saturating_sub(height / 2)
+            .min(BOARD_HEIGHT - height);
+        let scroll_x = cursor_col
+            .saturating_sub(width / 2)
+            .min(BOARD_WIDTH - width);
+        let board_area = Rect {
+            x: area.x + (area.width - width as u16) / 2,
+            y: area.y + (area.height - height as u16) / 2,
+            width: width as u16,
+            height: height as u16,
+        };
+        (board_area, scroll_y, scroll_x)
+    }
+
+    /**
+     * Move the cursor to the cell at a screen position, when the widget is rendered in
+     * an area. Positions outside the used cells are ignored.
+     */
+    pub fn select_at(&mut self, area: Rect, x: u16, y: u16) {
+        let (board_area, scroll_y, scroll_x) = self.view(area);
+        if !board_area.contains(Position::new(x, y)) {
+            return;
+        }
+        let line = (y - board_area.y) as usize + scroll_y;
+        let col = (x - board_area.x) as usize + scroll_x;
+        // Every fourth line and every seventh column is the gap between boxes.
+        if line % 4 == 3 || col % 7 == 6 {
+            return;
+        }
+        let (r, c) = (line / 4 * 3 + line % 4, col / 7 * 3 + col % 7 / 2);
+        if r < SAMURAI_SIZE && c < SAMURAI_SIZE && SamuraiMatrix::is_cell_used(r, c) {
+            self.cursor = (r, c);
+        }
+    }
+
+    pub fn new(matrix: SamuraiMatrix) -> Self {
+        let mut is_original_matrix = vec![false; SAMURAI_SIZE * SAMURAI_SIZE];
+        for (i, mut_ref) in is_original_matrix.iter_mut().enumerate() {
+            *mut_ref = matrix.get_value(i / SAMURAI_SIZE, i % SAMURAI_SIZE) != 0;
+        }
+        SamuraiWidget {
+            matrix,
+            is_original_matrix,
+            cursor: (0, 0),
+        }
+    }
+
+    pub fn matrix(&self) -> &SamuraiMatrix {
+        &self.matrix
+    }
+
+    /**
+     * Determine whether any digit has been entered besides the givens.
+     */
+    pub fn has_entries(&self) -> bool {
+        (0..SAMURAI_SIZE * SAMURAI_SIZE).any(|i| {
+            !self.is_original_matrix[i]
+                && self.matrix.get_value(i / SAMURAI_SIZE, i % SAMURAI_SIZE) != 0
+        })
+    }
+
+    /**
+     * Set the value on the current cursor position.
+     */
+    pub fn fill_value(&mut self, value: u32) {
+        let (r, c) = self.cursor;
+        if self.is_original_matrix[r * SAMURAI_SIZE + c] {
+            return;
+        }
+        self.matrix.set_value(r, c, value.try_into().unwrap());
+    }
+
+    /**
+     * Reset the matrix to the original state.
+     */
+    pub fn reset_matrix(&mut self) {
+        for i in 0..SAMURAI_SIZE * SAMURAI_SIZE {
+            if !self.is_original_matrix[i] {
+                self.matrix.set_value(i / SAMURAI_SIZE, i % SAMURAI_SIZE, 0);
+            }
+        }
+    }
+
+    pub fn solve_matrix(&mut self) {
+        solve_samurai(&mut self.matrix);
+    }
+
+    /**
+     * Move the cursor by one step, skipping the unused cells between sub-grids.
+     */
+    pub fn move_cursor(&mut self, dr: isize, dc: isize) {
+        let (mut r, mut c) = self.cursor;
+        loop {
+            let (Some(nr), Some(nc)) = (r.checked_add_signed(dr), c.checked_add_signed(dc)) else {
+                return;
+            };
+            if nr >= SAMURAI_SIZE || nc >= SAMURAI_SIZE {
+                return;
+            }
+            (r, c) = (nr, nc);
+            if SamuraiMatrix::is_cell_used(r, c) {
+                self.cursor = (r, c);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_at() {
+        let mut widget = SamuraiWidget::new(SamuraiMatrix::new());
+        // The whole 49x28 board fits and is centered at (5, 1), without scrolling.
+        let area = Rect::new(0, 0, 60, 30);
+        for (x, y, cell) in [
+            (5, 1, (0, 0)),
+            (6, 1, (0, 0)),
+            (7, 1, (0, 1)),
+            (12, 5, (3, 3)),
+            (19, 13, (9, 6)),
+            (52, 27, (20, 20)),
+        ] {
+            widget.select_at(area, x, y);
+            assert_eq!(widget.cursor, cell, "click at ({}, {})", x, y);
+        }
+        widget.cursor = (0, 0);
+        for (x, y) in [
+            // The blank column and line between boxes.
+            (11, 1),
+            (5, 4),
+            // The unused cells between the corner grids, and beside the center one.
+            (26, 1),
+            (5, 13),
+            // Outside the board.
+            (4, 1),
+            (54, 28),
+            (5, 0),
+        ] {
+            widget.select_at(area, x, y);
+            assert_eq!(widget.cursor, (0, 0), "click at ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn test_select_at_scrolled() {
+        let mut widget = SamuraiWidget::new(SamuraiMatrix::new());
+        // With the cursor in the bottom-right corner, the 20x10 view shows lines 18 to 27
+        // and columns 29 to 48 of the board.
+        let area = Rect::new(10, 5, 20, 10);
+        widget.cursor = (20, 20);
+        assert_eq!(widget.view(area), (area, 18, 29));
+        for (x, y, cell) in [
+            (10, 5, (14, 12)),
+            (12, 5, (14, 13)),
+            (27, 14, (20, 20)),
+            (20, 9, (17, 17)),
+        ] {
+            widget.select_at(area, x, y);
+            assert_eq!(widget.cursor, cell, "click at ({}, {})", x, y);
+            // Keep the view in place for the next click.
+            widget.cursor = (20, 20);
+        }
+        // Column 34 and line 19 of the board are gaps between boxes.
+        widget.select_at(area, 15, 5);
+        widget.select_at(area, 10, 6);
+        assert_eq!(widget.cursor, (20, 20));
+    }
+}
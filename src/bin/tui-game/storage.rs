@@ -0,0 +1,68 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use sudoku_lib::CalendarDate;
+
+const DAILY_COMPLETED_FILE: &str = "daily_completed.txt";
+const SAVED_GAME_FILE: &str = "saved_game.txt";
+
+/**
+ * Directory where the game keeps its data, or `None` if no home directory is known.
+ */
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if let Some(dir) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        PathBuf::from(dir)
+    } else if let Some(home) = std::env::var_os("HOME").filter(|d| !d.is_empty()) {
+        PathBuf::from(home).join(".local").join("share")
+    } else {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    };
+    Some(base.join("sudoku"))
+}
+
+/**
+ * Determine whether the daily puzzle of the date has been completed before.
+ */
+pub fn is_daily_completed(date: CalendarDate) -> bool {
+    let Some(path) = data_dir().map(|dir| dir.join(DAILY_COMPLETED_FILE)) else {
+        return false;
+    };
+    let date = date.to_string();
+    fs::read_to_string(path).is_ok_and(|content| content.lines().any(|line| line.trim() == date))
+}
+
+/**
+ * Remember that the daily puzzle of the date has been completed.
+ */
+pub fn record_daily_completed(date: CalendarDate) -> io::Result<()> {
+    if is_daily_completed(date) {
+        return Ok(());
+    }
+    let dir =
+        data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    fs::create_dir_all(&dir)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(DAILY_COMPLETED_FILE))?;
+    writeln!(file, "{}", date)
+}
+
+/**
+ * Path of the game saved with the save command, or on quit.
+ */
+pub fn saved_game_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SAVED_GAME_FILE))
+}
+
+/**
+ * Write a saved game, replacing the file only once fully written.
+ */
+pub fn write_saved_game(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/**
+ * Number of items in flight per worker thread: read but not yet passed to the sink.
+ * This window bounds the memory use of `process_in_parallel`, whatever the size of the
+ * input, and lets the workers run ahead of an item that takes long to process.
+ */
+const ITEMS_PER_THREAD: usize = 64;
+
+/**
+ * Statistics of a `process_in_parallel` run.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BulkStats {
+    /// The number of items processed.
+    pub items: usize,
+    /// The number of worker threads used.
+    pub threads: usize,
+    /// The wall-clock time of the whole run, including reading and writing.
+    pub elapsed: Duration,
+}
+
+impl BulkStats {
+    /**
+     * Return the number of items processed per second.
+     */
+    pub fn throughput(&self) -> f64 {
+        self.items as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+/**
+ * Process a stream of items on worker threads, passing the results to `sink` in input order.
+ *
+ * Items are read one at a time on the calling thread and streamed to the workers through
+ * bounded channels, so the input can be a reader over a file of any size, such as a
+ * `PuzzleReader`. Results are put back in input order by a reorder buffer before they
+ * reach `sink`, which runs on its own thread. At most `threads * ITEMS_PER_THREAD` items
+ * are in flight at a time.
+ *
+ * Processing stops at the first error from `items` or `sink`, after the results of the
+ * items read before the error have been passed to `sink`. Items that can fail on their
+ * own, such as malformed records, should carry their error in `P` so that the other
+ * items are still processed.
+ *
+ * A panic in `f` stops the processing and is resumed on the calling thread, once the
+ * other workers are done.
+ *
+ * # Arguments
+ * * `items` the input items, or errors
+ * * `threads` the number of worker threads; `0` uses the available parallelism
+ * * `f` the processing of one item
+ * * `sink` the consumer of the results
+ */
+pub fn process_in_parallel<P, T, E, I, F, S>(
+    items: I,
+    threads: usize,
+    f: F,
+    mut sink: S,
+) -> Result<BulkStats, E>
+where
+    I: IntoIterator<Item = Result<P, E>>,
+    P: Send,
+    T: Send,
+    E: Send,
+    F: Fn(&P) -> T + Sync,
+    S: FnMut(T) -> Result<(), E> + Send,
+{
+    let start = Instant::now();
+    let threads = if threads == 0 {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+    let window = threads * ITEMS_PER_THREAD;
+    let (work_tx, work_rx) = mpsc::sync_channel::<(usize, P)>(window);
+    let work_rx = Mutex::new(work_rx);
+    let (done_tx, done_rx) = mpsc::sync_channel::<(usize, thread::Result<T>)>(window);
+    // One permit per item in flight, returned once the result went to the sink.
+    let (permit_tx, permit_rx) = mpsc::sync_channel::<()>(window);
+    for _ in 0..window {
+        permit_tx
+            .send(())
+            .expect("the permit channel holds the whole window");
+    }
+    let stop = AtomicBool::new(false);
+
+    let (read_error, sink_result) = thread::scope(|scope| {
+        for _ in 0..threads {
+            let done_tx = done_tx.clone();
+            let (work_rx, f) = (&work_rx, &f);
+            scope.spawn(move || {
+                loop {
+                    let Ok((idx, item)) = work_rx.lock().unwrap().recv() else {
+                        break;
+                    };
+                    // A panic is passed on as a result, so that the item is not lost and
+                    // the writer stops instead of waiting for it.
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&item)));
+                    if done_tx.send((idx, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(done_tx);
+
+        let stop = &stop;
+        let writer = scope.spawn(move || {
+            let mut pending = BTreeMap::new();
+            let mut next = 0;
+            for (idx, result) in done_rx {
+                match result {
+                    Ok(result) => pending.insert(idx, result),
+                    Err(payload) => {
+                        stop.store(true, Ordering::Relaxed);
+                        return Err(payload);
+                    }
+                };
+                while let Some(result) = pending.remove(&next) {
+                    if let Err(err) = sink(result) {
+                        stop.store(true, Ordering::Relaxed);
+                        return Ok(Err(err));
+                    }
+                    next += 1;
+                    // The reader may already be gone after an input error.
+                    let _ = permit_tx.send(());
+                }
+            }
+            Ok(Ok(next))
+        });
+
+        let mut read_error = None;
+        let mut items = items.into_iter().enumerate();
+        while permit_rx.recv().is_ok() && !stop.load(Ordering::Relaxed) {
+            let Some((idx, item)) = items.next() else {
+                break;
+            };
+            match item {
+                Ok(item) => {
+                    if work_tx.send((idx, item)).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    read_error = Some(err);
+                    break;
+                }
+            }
+        }
+        drop(work_tx);
+        let sink_result = writer.join().expect("the sink thread does not panic");
+        (read_error, sink_result)
+    });
+    let processed = match sink_result {
+        Ok(result) => result?,
+        Err(payload) => panic::resume_unwind(payload),
+    };
+    if let Some(err) = read_error {
+        return Err(err);
+    }
+    Ok(BulkStats {
+        items: processed,
+        threads,
+        elapsed: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_process_in_parallel_keeps_order() {
+        let items = (0..1000u64).map(Ok::<u64, String>);
+        let mut results = vec![];
+        let stats = process_in_parallel(
+            items,
+            4,
+            |n| {
+                // Make later items finish first.
+                thread::sleep(Duration::from_micros(1000 - n));
+                n * 2
+            },
+            |n| {
+                results.push(n);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(stats.items, 1000);
+        assert_eq!(stats.threads, 4);
+        assert_eq!(results, (0..1000).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_process_in_parallel_stops_on_error() {
+        let items = (0..10).map(|n| if n == 5 { Err(n) } else { Ok(n) });
+        let mut results = vec![];
+        let result = process_in_parallel(
+            items,
+            2,
+            |n| *n,
+            |n| {
+                results.push(n);
+                Ok(())
+            },
+        );
+        assert_eq!(result, Err(5));
+        assert_eq!(results, vec![0, 1, 2, 3, 4]);
+
+        let result = process_in_parallel(
+            (0..10).map(Ok),
+            2,
+            |n| *n,
+            |n| {
+                if n == 3 { Err(n) } else { Ok(()) }
+            },
+        );
+        assert_eq!(result, Err(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "bad item")]
+    fn test_process_in_parallel_resumes_worker_panic() {
+        let _ = process_in_parallel(
+            (0..1000).map(Ok::<u64, String>),
+            2,
+            |n| {
+                if *n == 100 {
+                    panic!("bad item");
+                }
+                *n
+            },
+            |_| Ok(()),
+        );
+    }
+
+    #[test]
+    fn test_process_in_parallel_bounds_items_in_flight() {
+        let read = AtomicUsize::new(0);
+        let written = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let items = (0..2000u64).map(|n| {
+            let in_flight =
+                read.fetch_add(1, Ordering::SeqCst) + 1 - written.load(Ordering::SeqCst);
+            max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            Ok::<u64, String>(n)
+        });
+        let stats = process_in_parallel(
+            items,
+            2,
+            |n| {
+                // The first item is slow, so every later one waits in the reorder buffer.
+                if *n == 0 {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                *n
+            },
+            |_| {
+                written.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(stats.items, 2000);
+        let in_flight = max_in_flight.load(Ordering::SeqCst);
+        assert!(
+            in_flight <= 2 * ITEMS_PER_THREAD,
+            "{} items in flight",
+            in_flight
+        );
+    }
+}
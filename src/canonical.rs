@@ -0,0 +1,196 @@
+use crate::matrix::SudokuMatrix;
+
+/**
+ * All 1296 orderings of the rows (or columns) of a grid that keep the band (or
+ * stack) structure: 6 orders of the bands times 6 orders of the rows inside each band.
+ */
+fn line_permutations() -> Vec<[usize; 9]> {
+    const PERMS3: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut perms = vec![];
+    for bands in PERMS3 {
+        for p0 in PERMS3 {
+            for p1 in PERMS3 {
+                for p2 in PERMS3 {
+                    let inner = [p0, p1, p2];
+                    perms.push(std::array::from_fn(|idx| {
+                        bands[idx / 3] * 3 + inner[idx / 3][idx % 3]
+                    }));
+                }
+            }
+        }
+    }
+    perms
+}
+
+/**
+ * Compute the canonical form of a Sudoku matrix.
+ *
+ * Two matrices that can be mapped onto each other by relabeling the digits, permuting
+ * rows inside a band, columns inside a stack, swapping bands or stacks, or transposing,
+ * have the same canonical form. The canonical form is the lexicographically smallest
+ * such transform in row-major order, where empty cells count as `0` and digits are
+ * relabeled in order of first appearance.
+ */
+pub fn canonicalize(mat: &SudokuMatrix) -> SudokuMatrix {
+    let perms = line_permutations();
+    let mut best = [u8::MAX; 81];
+    let mut candidate = [0u8; 81];
+    for transpose in [false, true] {
+        let grid: [[u8; 9]; 9] = std::array::from_fn(|r| {
+            std::array::from_fn(|c| {
+                if transpose {
+                    mat.get_value(c, r)
+                } else {
+                    mat.get_value(r, c)
+                }
+            })
+        });
+        for row_perm in perms.iter() {
+            for col_perm in perms.iter() {
+                let mut labels = [0u8; 10];
+                let mut next_label = 1;
+                let mut is_smaller = false;
+                let mut is_larger = false;
+                for (i, slot) in candidate.iter_mut().enumerate() {
+                    let v = grid[row_perm[i / 9]][col_perm[i % 9]];
+                    if v != 0 && labels[v as usize] == 0 {
+                        labels[v as usize] = next_label;
+                        next_label += 1;
+                    }
+                    *slot = labels[v as usize];
+                    if !is_smaller {
+                        if *slot > best[i] {
+                            is_larger = true;
+                            break;
+                        }
+                        is_smaller = *slot < best[i];
+                    }
+                }
+                if is_smaller && !is_larger {
+                    best = candidate;
+                }
+            }
+        }
+    }
+    let mut canonical = SudokuMatrix::new();
+    for (i, v) in best.iter().enumerate() {
+        canonical.set_value(i / 9, i % 9, *v);
+    }
+    canonical
+}
+
+/**
+ * Determine whether two matrices are transforms of each other under the Sudoku symmetries.
+ */
+pub fn is_equivalent(a: &SudokuMatrix, b: &SudokuMatrix) -> bool {
+    if a.filled_count() != b.filled_count() {
+        return false;
+    }
+    canonicalize(a) == canonicalize(b)
+}
+
+/**
+ * Hash the canonical form of a matrix, so that equivalent matrices get the same hash.
+ *
+ * The hash is stable across platforms and program runs, so it can be stored in files.
+ */
+pub fn canonical_hash(mat: &SudokuMatrix) -> u64 {
+    hash_canonical(&canonicalize(mat))
+}
+
+/**
+ * Hash a matrix already in canonical form, as returned by `canonicalize`, without
+ * computing the canonical form again.
+ *
+ * `hash_canonical(&canonicalize(mat))` is `canonical_hash(mat)`.
+ */
+pub fn hash_canonical(canonical: &SudokuMatrix) -> u64 {
+    fnv1a(canonical.to_line().as_bytes())
+}
+
+/**
+ * The 64-bit FNV-1a hash, which unlike the standard library hasher is stable across
+ * platforms and Rust versions.
+ */
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::solve_sudoku;
+
+    fn puzzle() -> SudokuMatrix {
+        let mut mat = SudokuMatrix::new();
+        assert!(solve_sudoku(&mut mat, false));
+        for i in 0..81 {
+            if i % 3 != 0 {
+                mat.set_value(i / 9, i % 9, 0);
+            }
+        }
+        mat
+    }
+
+    #[test]
+    fn test_line_permutations() {
+        let perms = line_permutations();
+        assert_eq!(perms.len(), 1296);
+        assert!(perms.contains(&[0, 1, 2, 3, 4, 5, 6, 7, 8]));
+        assert!(perms.contains(&[8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        assert!(!perms.contains(&[3, 1, 2, 0, 4, 5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_equivalent_after_transform() {
+        let mat = puzzle();
+        // Transpose, swap the first two bands and relabel 1 <-> 2.
+        let mut transformed = SudokuMatrix::new();
+        for r in 0..9 {
+            for c in 0..9 {
+                let v = match mat.get_value(c, r) {
+                    1 => 2,
+                    2 => 1,
+                    v => v,
+                };
+                let new_r = if r < 6 { (r + 3) % 6 } else { r };
+                transformed.set_value(new_r, c, v);
+            }
+        }
+        assert_eq!(canonicalize(&mat), canonicalize(&transformed));
+        assert!(is_equivalent(&mat, &transformed));
+        assert_eq!(canonical_hash(&mat), canonical_hash(&transformed));
+        assert_eq!(
+            hash_canonical(&canonicalize(&transformed)),
+            canonical_hash(&mat)
+        );
+    }
+
+    #[test]
+    fn test_not_equivalent() {
+        let mat = puzzle();
+        let mut other = mat.clone();
+        other.set_value(0, 0, 0);
+        assert!(!is_equivalent(&mat, &other));
+
+        // Same clue count and digits, but the 1 and the 2 share a row in only one of them,
+        // which no transform can change.
+        let mut a = SudokuMatrix::new();
+        a.set_value(0, 0, 1);
+        a.set_value(0, 1, 2);
+        let mut b = SudokuMatrix::new();
+        b.set_value(0, 0, 1);
+        b.set_value(4, 4, 2);
+        assert!(!is_equivalent(&a, &b));
+        assert_ne!(canonical_hash(&a), canonical_hash(&b));
+    }
+}
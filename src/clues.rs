@@ -0,0 +1,579 @@
+use crate::matrix::SudokuMatrix;
+use crate::search::{ClassicGrid, Constraints, Search};
+use rand::rngs::ThreadRng;
+use std::fmt;
+use std::str::FromStr;
+
+/**
+ * Direction in which a little killer clue points into the grid.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagonalDirection {
+    DownRight,
+    DownLeft,
+    UpRight,
+    UpLeft,
+}
+
+impl DiagonalDirection {
+    pub const ALL: [DiagonalDirection; 4] = [
+        DiagonalDirection::DownRight,
+        DiagonalDirection::DownLeft,
+        DiagonalDirection::UpRight,
+        DiagonalDirection::UpLeft,
+    ];
+
+    /**
+     * Row and column step taken when following the diagonal.
+     */
+    pub fn step(&self) -> (isize, isize) {
+        match self {
+            DiagonalDirection::DownRight => (1, 1),
+            DiagonalDirection::DownLeft => (1, -1),
+            DiagonalDirection::UpRight => (-1, 1),
+            DiagonalDirection::UpLeft => (-1, -1),
+        }
+    }
+}
+
+impl fmt::Display for DiagonalDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DiagonalDirection::DownRight => "down-right",
+            DiagonalDirection::DownLeft => "down-left",
+            DiagonalDirection::UpRight => "up-right",
+            DiagonalDirection::UpLeft => "up-left",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for DiagonalDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DiagonalDirection::ALL
+            .into_iter()
+            .find(|direction| direction.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown direction `{}`, expected one of: down-right, down-left, up-right, up-left",
+                    s
+                )
+            })
+    }
+}
+
+/**
+ * A little killer clue: the sum of the digits on a diagonal of the grid.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LittleKillerClue {
+    start: (usize, usize),
+    direction: DiagonalDirection,
+    sum: u32,
+}
+
+impl LittleKillerClue {
+    /**
+     * Creates a little killer clue.
+     *
+     * # Arguments
+     * * `start` the first cell of the diagonal, on the edge of the grid next to the clue
+     * * `direction` the direction of the diagonal from `start`
+     * * `sum` the sum of the digits on the diagonal
+     *
+     * # Returns
+     * The clue, or an error when `start` is not in the grid or the cell before it in
+     * `direction` is, since the clue is written outside the border.
+     */
+    pub fn new(
+        start: (usize, usize),
+        direction: DiagonalDirection,
+        sum: u32,
+    ) -> Result<Self, String> {
+        let (dr, dc) = direction.step();
+        let before = (start.0 as isize - dr, start.1 as isize - dc);
+        if start.0 >= 9
+            || start.1 >= 9
+            || ((0..9).contains(&before.0) && (0..9).contains(&before.1))
+        {
+            return Err(format!(
+                "a {} diagonal cannot start at ({}, {})",
+                direction, start.0, start.1
+            ));
+        }
+        Ok(Self {
+            start,
+            direction,
+            sum,
+        })
+    }
+
+    /**
+     * The first cell of the diagonal, on the edge of the grid next to the clue.
+     */
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    pub fn direction(&self) -> DiagonalDirection {
+        self.direction
+    }
+
+    pub fn sum(&self) -> u32 {
+        self.sum
+    }
+
+    /**
+     * Return the cells on the diagonal, starting from `start`.
+     */
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let (dr, dc) = self.direction.step();
+        let mut cells = vec![];
+        let (mut r, mut c) = (self.start.0 as isize, self.start.1 as isize);
+        while (0..9).contains(&r) && (0..9).contains(&c) {
+            cells.push((r as usize, c as usize));
+            r += dr;
+            c += dc;
+        }
+        cells
+    }
+
+    /**
+     * Determine whether the cell `(r, c)` is on the diagonal.
+     */
+    fn contains(&self, r: usize, c: usize) -> bool {
+        let (dr, dc) = self.direction.step();
+        // The number of steps from `start` along the row and along the column.
+        let steps_r = (r as isize - self.start.0 as isize) * dr;
+        let steps_c = (c as isize - self.start.1 as isize) * dc;
+        steps_r >= 0 && steps_r == steps_c
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/**
+ * Clues written outside the border of the grid.
+ *
+ * * Sandwich clues give, for each row or column, the sum of the digits between the 1 and the 9.
+ * * Skyscraper clues give the number of digits visible from that side, where a larger digit
+ *   hides every smaller digit behind it.
+ * * Little killer clues give the sum of the digits along a diagonal.
+ *
+ * A `None` entry means there is no clue for that row or column.
+ */
+pub struct OutsideClues {
+    pub sandwich_rows: [Option<u8>; 9],
+    pub sandwich_cols: [Option<u8>; 9],
+    pub skyscraper_top: [Option<u8>; 9],
+    pub skyscraper_bottom: [Option<u8>; 9],
+    pub skyscraper_left: [Option<u8>; 9],
+    pub skyscraper_right: [Option<u8>; 9],
+    pub little_killers: Vec<LittleKillerClue>,
+}
+
+impl OutsideClues {
+    /**
+     * Creates an empty set of clues.
+     */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Derive the sandwich clues of every row and column from a solved matrix.
+     */
+    pub fn sandwich_from_solution(mat: &SudokuMatrix) -> Self {
+        let values = matrix_values(mat);
+        let mut clues = Self::new();
+        for idx in 0..9 {
+            clues.sandwich_rows[idx] = sandwich_sum(&row_values(&values, idx));
+            clues.sandwich_cols[idx] = sandwich_sum(&col_values(&values, idx));
+        }
+        clues
+    }
+
+    /**
+     * Derive the skyscraper clues of every side from a solved matrix.
+     */
+    pub fn skyscraper_from_solution(mat: &SudokuMatrix) -> Self {
+        let values = matrix_values(mat);
+        let mut clues = Self::new();
+        for idx in 0..9 {
+            let mut row = row_values(&values, idx);
+            let mut col = col_values(&values, idx);
+            clues.skyscraper_left[idx] = Some(visible_count(&row));
+            clues.skyscraper_top[idx] = Some(visible_count(&col));
+            row.reverse();
+            col.reverse();
+            clues.skyscraper_right[idx] = Some(visible_count(&row));
+            clues.skyscraper_bottom[idx] = Some(visible_count(&col));
+        }
+        clues
+    }
+
+    /**
+     * Determine whether there is no clue at all.
+     */
+    pub fn is_empty(&self) -> bool {
+        *self == Self::new()
+    }
+
+    /**
+     * Determine whether a partially-filled matrix can still satisfy the clues.
+     *
+     * Only clues whose cells are filled enough to be decided are checked, so
+     * `true` does not mean that a solution exists.
+     */
+    pub fn is_compatible_with(&self, mat: &SudokuMatrix) -> bool {
+        let values = matrix_values(mat);
+        (0..9)
+            .all(|idx| self.is_row_compatible(&values, idx) && self.is_col_compatible(&values, idx))
+            && self
+                .little_killers
+                .iter()
+                .all(|clue| is_little_killer_compatible(&values, clue))
+    }
+
+    /**
+     * Determine whether a matrix is a complete, conflict-free solution satisfying every clue.
+     */
+    pub fn is_satisfied_by(&self, mat: &SudokuMatrix) -> bool {
+        mat.is_complete() && mat.is_compatible() && self.is_compatible_with(mat)
+    }
+
+    fn is_row_compatible(&self, values: &[u8], r: usize) -> bool {
+        let mut vals = row_values(values, r);
+        if !is_sandwich_compatible(&vals, self.sandwich_rows[r])
+            || !is_skyscraper_compatible(&vals, self.skyscraper_left[r])
+        {
+            return false;
+        }
+        vals.reverse();
+        is_skyscraper_compatible(&vals, self.skyscraper_right[r])
+    }
+
+    fn is_col_compatible(&self, values: &[u8], c: usize) -> bool {
+        let mut vals = col_values(values, c);
+        if !is_sandwich_compatible(&vals, self.sandwich_cols[c])
+            || !is_skyscraper_compatible(&vals, self.skyscraper_top[c])
+        {
+            return false;
+        }
+        vals.reverse();
+        is_skyscraper_compatible(&vals, self.skyscraper_bottom[c])
+    }
+
+    /**
+     * Check only the clues that involve the cell `(r, c)` of the 81 row-major `values`.
+     */
+    fn is_cell_compatible(&self, values: &[u8], r: usize, c: usize) -> bool {
+        self.is_row_compatible(values, r)
+            && self.is_col_compatible(values, c)
+            && self
+                .little_killers
+                .iter()
+                .filter(|clue| clue.contains(r, c))
+                .all(|clue| is_little_killer_compatible(values, clue))
+    }
+}
+
+/**
+ * Return the values of the cells in row-major order, as `Search` holds them.
+ */
+fn matrix_values(mat: &SudokuMatrix) -> [u8; 81] {
+    std::array::from_fn(|cell| mat.get_value(cell / 9, cell % 9))
+}
+
+fn row_values(values: &[u8], r: usize) -> [u8; 9] {
+    std::array::from_fn(|c| values[r * 9 + c])
+}
+
+fn col_values(values: &[u8], c: usize) -> [u8; 9] {
+    std::array::from_fn(|r| values[r * 9 + c])
+}
+
+fn sandwich_sum(vals: &[u8; 9]) -> Option<u8> {
+    let one = vals.iter().position(|v| *v == 1)?;
+    let nine = vals.iter().position(|v| *v == 9)?;
+    Some(vals[one.min(nine) + 1..one.max(nine)].iter().sum())
+}
+
+fn visible_count(vals: &[u8; 9]) -> u8 {
+    let mut highest = 0;
+    let mut count = 0;
+    for v in vals {
+        if *v > highest {
+            highest = *v;
+            count += 1;
+        }
+    }
+    count
+}
+
+fn is_sandwich_compatible(vals: &[u8; 9], clue: Option<u8>) -> bool {
+    let Some(clue) = clue else {
+        return true;
+    };
+    let (Some(one), Some(nine)) = (
+        vals.iter().position(|v| *v == 1),
+        vals.iter().position(|v| *v == 9),
+    ) else {
+        return true;
+    };
+    let between = &vals[one.min(nine) + 1..one.max(nine)];
+    let filled_sum: u32 = between.iter().map(|v| *v as u32).sum();
+    let empty_cnt = between.iter().filter(|v| **v == 0).count() as u32;
+    // Digits between the 1 and the 9 can only range from 2 to 8.
+    filled_sum + empty_cnt * 2 <= clue as u32 && clue as u32 <= filled_sum + empty_cnt * 8
+}
+
+fn is_skyscraper_compatible(vals: &[u8; 9], clue: Option<u8>) -> bool {
+    let Some(clue) = clue else {
+        return true;
+    };
+    // Digits in the filled prefix are visible regardless of what comes after them.
+    let prefix_len = vals.iter().position(|v| *v == 0).unwrap_or(9);
+    let mut prefix = [0u8; 9];
+    prefix[..prefix_len].copy_from_slice(&vals[..prefix_len]);
+    let visible = visible_count(&prefix);
+    if prefix.contains(&9) {
+        visible == clue
+    } else {
+        visible < clue
+    }
+}
+
+fn is_little_killer_compatible(values: &[u8], clue: &LittleKillerClue) -> bool {
+    let cells = clue.cells();
+    let filled_sum: u32 = cells.iter().map(|(r, c)| values[r * 9 + c] as u32).sum();
+    let empty_cnt = cells.iter().filter(|(r, c)| values[r * 9 + c] == 0).count() as u32;
+    filled_sum + empty_cnt <= clue.sum && clue.sum <= filled_sum + empty_cnt * 9
+}
+
+/**
+ * The rules of a classic grid with outside clues for `Search`, checking the clues that
+ * involve every placed cell.
+ */
+struct ClueGrid<'a>(&'a OutsideClues);
+
+impl Constraints for ClueGrid<'_> {
+    fn cell_count(&self) -> usize {
+        ClassicGrid.cell_count()
+    }
+
+    fn units(&self) -> &[[usize; 9]] {
+        ClassicGrid.units()
+    }
+
+    fn units_of(&self, cell: usize) -> &[usize] {
+        ClassicGrid.units_of(cell)
+    }
+
+    fn allows(&self, values: &[u8], cell: usize) -> bool {
+        self.0.is_cell_compatible(values, cell / 9, cell % 9)
+    }
+}
+
+/**
+ * Run the search on a puzzle with outside clues.
+ *
+ * # Returns
+ * The number of solutions found, the first solution and whether the node budget ran out,
+ * or `None` when the givens already break the rules or the clues.
+ */
+fn search_with_clues(
+    mat: &SudokuMatrix,
+    clues: &OutsideClues,
+    limit: usize,
+    max_nodes: usize,
+) -> Option<(usize, Option<SudokuMatrix>, bool)> {
+    if !clues.is_compatible_with(mat) {
+        return None;
+    }
+    let givens: Vec<u8> = (0..81)
+        .map(|cell| mat.get_value(cell / 9, cell % 9))
+        .collect();
+    let grid = ClueGrid(clues);
+    let mut search = Search::new(&grid, &givens)?;
+    search.max_nodes = max_nodes;
+    let found = search.run::<ThreadRng>(limit, None);
+    let solution = search.solution().map(|cells| {
+        let mut solved = SudokuMatrix::new();
+        for (idx, v) in cells.iter().enumerate() {
+            solved.set_value(idx / 9, idx % 9, *v);
+        }
+        solved
+    });
+    Some((found, solution, search.is_exhausted()))
+}
+
+/**
+ * Solve a partially-filled Sudoku puzzle that also has outside clues.
+ *
+ * Return true on success, false on failure. On failure the matrix is left untouched.
+ */
+pub fn solve_with_clues(mat: &mut SudokuMatrix, clues: &OutsideClues) -> bool {
+    match search_with_clues(mat, clues, 1, usize::MAX) {
+        Some((_, Some(solved), _)) => {
+            *mat = solved;
+            true
+        }
+        _ => false,
+    }
+}
+
+/**
+ * Count the solutions of a puzzle with outside clues, stopping once `limit` solutions were found.
+ */
+pub fn count_solutions_with_clues(mat: &SudokuMatrix, clues: &OutsideClues, limit: usize) -> usize {
+    search_with_clues(mat, clues, limit, usize::MAX).map_or(0, |(found, _, _)| found)
+}
+
+/**
+ * Check that a puzzle with outside clues has exactly one solution, giving up after
+ * `max_nodes` search nodes.
+ *
+ * Return `None` when the budget ran out before the answer was known.
+ */
+pub(crate) fn has_unique_solution_with_clues(
+    mat: &SudokuMatrix,
+    clues: &OutsideClues,
+    max_nodes: usize,
+) -> Option<bool> {
+    match search_with_clues(mat, clues, 2, max_nodes) {
+        None => Some(false),
+        Some((_, _, true)) => None,
+        Some((found, _, false)) => Some(found == 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solved_matrix() -> SudokuMatrix {
+        let mut mat = SudokuMatrix::new();
+        assert!(crate::solver::solve_sudoku(&mut mat, false));
+        mat
+    }
+
+    #[test]
+    fn test_sandwich_sum() {
+        assert_eq!(sandwich_sum(&[1, 2, 3, 9, 4, 5, 6, 7, 8]), Some(5));
+        assert_eq!(sandwich_sum(&[9, 1, 2, 3, 4, 5, 6, 7, 8]), Some(0));
+        assert_eq!(sandwich_sum(&[2, 9, 3, 4, 5, 6, 7, 8, 1]), Some(33));
+    }
+
+    #[test]
+    fn test_visible_count() {
+        assert_eq!(visible_count(&[1, 2, 3, 4, 5, 6, 7, 8, 9]), 9);
+        assert_eq!(visible_count(&[9, 1, 2, 3, 4, 5, 6, 7, 8]), 1);
+        assert_eq!(visible_count(&[3, 1, 5, 2, 9, 4, 6, 7, 8]), 3);
+    }
+
+    #[test]
+    fn test_little_killer_cells() {
+        let clue = LittleKillerClue::new((0, 6), DiagonalDirection::DownRight, 10).unwrap();
+        assert_eq!(clue.cells(), vec![(0, 6), (1, 7), (2, 8)]);
+        assert!(clue.contains(2, 8));
+        assert!(!clue.contains(1, 5));
+        let clue = LittleKillerClue::new((8, 3), DiagonalDirection::UpLeft, 10).unwrap();
+        assert_eq!(clue.cells(), vec![(8, 3), (7, 2), (6, 1), (5, 0)]);
+        assert!(clue.contains(6, 1));
+        assert!(!clue.contains(0, 0));
+    }
+
+    #[test]
+    fn test_little_killer_start_is_on_the_edge() {
+        assert!(LittleKillerClue::new((0, 0), DiagonalDirection::DownRight, 45).is_ok());
+        assert!(LittleKillerClue::new((4, 8), DiagonalDirection::DownLeft, 20).is_ok());
+        // The clue would be written inside the grid.
+        assert!(LittleKillerClue::new((4, 4), DiagonalDirection::DownRight, 20).is_err());
+        assert!(LittleKillerClue::new((0, 0), DiagonalDirection::UpLeft, 5).is_err());
+        assert!(LittleKillerClue::new((9, 0), DiagonalDirection::UpRight, 5).is_err());
+    }
+
+    #[test]
+    fn test_clues_from_solution_are_satisfied() {
+        let mat = solved_matrix();
+        assert!(OutsideClues::sandwich_from_solution(&mat).is_satisfied_by(&mat));
+        assert!(OutsideClues::skyscraper_from_solution(&mat).is_satisfied_by(&mat));
+    }
+
+    #[test]
+    fn test_wrong_clue_is_rejected() {
+        let mat = solved_matrix();
+        let mut clues = OutsideClues::sandwich_from_solution(&mat);
+        clues.sandwich_rows[0] = clues.sandwich_rows[0].map(|v| v + 1);
+        assert!(!clues.is_compatible_with(&mat));
+    }
+
+    /**
+     * Return the solution with every other cell emptied.
+     */
+    fn checkerboard_puzzle(full: &SudokuMatrix) -> SudokuMatrix {
+        let mut puzzle = full.clone();
+        for r in 0..9 {
+            for c in 0..9 {
+                if (r + c) % 2 == 0 {
+                    puzzle.set_value(r, c, 0);
+                }
+            }
+        }
+        puzzle
+    }
+
+    #[test]
+    fn test_solve_with_sandwich_clues() {
+        let full = solved_matrix();
+        let clues = OutsideClues::sandwich_from_solution(&full);
+        let mut puzzle = checkerboard_puzzle(&full);
+        assert!(solve_with_clues(&mut puzzle, &clues));
+        assert!(clues.is_satisfied_by(&puzzle));
+    }
+
+    #[test]
+    fn test_solve_with_skyscraper_clues() {
+        let full = solved_matrix();
+        let clues = OutsideClues::skyscraper_from_solution(&full);
+        let mut puzzle = checkerboard_puzzle(&full);
+        assert!(solve_with_clues(&mut puzzle, &clues));
+        assert!(clues.is_satisfied_by(&puzzle));
+
+        let mut wrong = clues.clone();
+        wrong.skyscraper_left[0] = wrong.skyscraper_left[0].map(|v| v % 9 + 1);
+        assert_eq!(
+            count_solutions_with_clues(&checkerboard_puzzle(&full), &wrong, 1),
+            0
+        );
+    }
+
+    #[test]
+    fn test_solve_with_little_killer_clues() {
+        let full = solved_matrix();
+        let mut clues = OutsideClues::new();
+        // Every diagonal from the top row, pointing down.
+        for c in 0..9 {
+            for direction in [DiagonalDirection::DownRight, DiagonalDirection::DownLeft] {
+                let mut clue = LittleKillerClue::new((0, c), direction, 0).unwrap();
+                clue.sum = clue
+                    .cells()
+                    .iter()
+                    .map(|(r, c)| full.get_value(*r, *c) as u32)
+                    .sum();
+                clues.little_killers.push(clue);
+            }
+        }
+        let mut puzzle = checkerboard_puzzle(&full);
+        assert!(solve_with_clues(&mut puzzle, &clues));
+        assert!(clues.is_satisfied_by(&puzzle));
+
+        clues.little_killers[3].sum += 1;
+        assert_eq!(
+            count_solutions_with_clues(&checkerboard_puzzle(&full), &clues, 1),
+            0
+        );
+    }
+}
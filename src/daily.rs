@@ -0,0 +1,183 @@
+use crate::canonical::fnv1a;
+use crate::generator::create_rated_matrix;
+use crate::rating::{Difficulty, RatedPuzzle};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/**
+ * A day of the proleptic Gregorian calendar.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CalendarDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl CalendarDate {
+    /**
+     * Create a date, or return `None` if the day does not exist.
+     */
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        let is_leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if is_leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > days_in_month {
+            return None;
+        }
+        Some(CalendarDate { year, month, day })
+    }
+
+    /**
+     * Today's date in UTC, so that every user sees the same date at the same moment.
+     */
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_days_since_epoch((secs / 86400) as i64)
+    }
+
+    /**
+     * Convert a number of days since 1970-01-01 into a date.
+     */
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        // Civil-from-days conversion on 400-year eras starting on March 1st.
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        CalendarDate { year, month, day }
+    }
+
+    /**
+     * Return the number of days since 1970-01-01.
+     */
+    pub fn days_since_epoch(&self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = (self.month as i64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    /**
+     * Return the day of the week, from `0` for Monday to `6` for Sunday.
+     */
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.days_since_epoch() + 3).rem_euclid(7) as u32
+    }
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for CalendarDate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid date `{}`, expected YYYY-MM-DD", s);
+        let parts: Vec<&str> = s.split('-').collect();
+        let [year, month, day] = parts[..] else {
+            return Err(err());
+        };
+        let year = year.parse().map_err(|_| err())?;
+        let month = month.parse().map_err(|_| err())?;
+        let day = day.parse().map_err(|_| err())?;
+        CalendarDate::new(year, month, day).ok_or_else(err)
+    }
+}
+
+/**
+ * Difficulty of the daily puzzle: easy on Monday, getting harder until the weekend.
+ */
+pub fn daily_difficulty(date: CalendarDate) -> Difficulty {
+    match date.weekday() {
+        0 => Difficulty::Easy,
+        1 | 2 => Difficulty::Medium,
+        3 | 4 => Difficulty::Hard,
+        _ => Difficulty::Expert,
+    }
+}
+
+/**
+ * Derive the seed of a daily puzzle from a stable hash of the date and salt.
+ */
+fn daily_seed(date: CalendarDate, salt: Option<&str>) -> u64 {
+    let key = format!("sudoku-daily:{}:{}", date, salt.unwrap_or(""));
+    fnv1a(key.as_bytes())
+}
+
+/**
+ * Generate the puzzle of the day.
+ *
+ * The same date and salt always give the same rated, unique puzzle, so every user sees
+ * the same puzzle without a server. The salt lets an app keep its own sequence of
+ * daily puzzles. `ChaCha8Rng` is used because, unlike `StdRng`, its output is stable
+ * across `rand` releases.
+ */
+pub fn daily_puzzle(date: CalendarDate, salt: Option<&str>) -> RatedPuzzle {
+    let mut rng = ChaCha8Rng::seed_from_u64(daily_seed(date, salt));
+    create_rated_matrix(daily_difficulty(date), &mut rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendar_date() {
+        let date = CalendarDate::new(2024, 2, 29).unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!("2024-02-29".parse(), Ok(date));
+        assert!(CalendarDate::new(2023, 2, 29).is_none());
+        assert!("2024-13-01".parse::<CalendarDate>().is_err());
+        assert_eq!(
+            CalendarDate::from_days_since_epoch(0).to_string(),
+            "1970-01-01"
+        );
+        assert_eq!(date.days_since_epoch(), 19782);
+        assert_eq!(CalendarDate::from_days_since_epoch(19782), date);
+        // 2024-02-29 was a Thursday.
+        assert_eq!(date.weekday(), 3);
+    }
+
+    #[test]
+    fn test_daily_puzzle_is_deterministic() {
+        let monday = CalendarDate::new(2024, 3, 4).unwrap();
+        let puzzle = daily_puzzle(monday, None);
+        assert_eq!(puzzle.rating.difficulty, Difficulty::Easy);
+        assert_eq!(daily_puzzle(monday, None), puzzle);
+        assert_ne!(daily_puzzle(monday, Some("other app")), puzzle);
+    }
+
+    #[test]
+    fn test_daily_puzzle_golden() {
+        // Pins the puzzle of one day, so that a change to the seeding, the RNG or the
+        // generator that would show players a different daily puzzle is noticed.
+        let date = CalendarDate::new(2024, 3, 4).unwrap();
+        assert_eq!(
+            daily_puzzle(date, None).puzzle.to_line(),
+            "..........238..6.......6.91..9....1.......52.6..3...8.....1...2.826.79...3...8.5."
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use crate::matrix::SudokuMatrix;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/**
+ * Text formats to read and write puzzles in.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PuzzleFormat {
+    /// One puzzle per line as 81 cells in row-major order, with `.` or `0` for empty
+    /// cells. Anything after the cells, separated by whitespace, `,` or `;`, is ignored.
+    #[default]
+    Line,
+    /// Nine lines of nine cells per puzzle. Characters other than cells, such as spaces,
+    /// `|`, `-` and `+`, are ignored, so boxes may be drawn between the cells.
+    Grid,
+}
+
+impl PuzzleFormat {
+    pub const ALL: [PuzzleFormat; 2] = [PuzzleFormat::Line, PuzzleFormat::Grid];
+
+    /**
+     * Write a puzzle in this format, without a trailing line break.
+     */
+    pub fn format(&self, mat: &SudokuMatrix) -> String {
+        match self {
+            PuzzleFormat::Line => mat.to_line(),
+            PuzzleFormat::Grid => {
+                let mut lines = vec![];
+                for r in 0..9 {
+                    if r == 3 || r == 6 {
+                        lines.push("------+-------+------".to_string());
+                    }
+                    let mut line = String::new();
+                    for c in 0..9 {
+                        if c == 3 || c == 6 {
+                            line.push_str("| ");
+                        }
+                        match mat.get_value(r, c) {
+                            0 => line.push('.'),
+                            v => line.push((b'0' + v) as char),
+                        }
+                        line.push(' ');
+                    }
+                    lines.push(line.trim_end().to_string());
+                }
+                lines.join("\n")
+            }
+        }
+    }
+
+    /**
+     * Guess the format from the first line holding cells: a line holding a whole
+     * puzzle means the `Line` format.
+     */
+    fn detect(line: &str) -> Option<PuzzleFormat> {
+        let cells = line.chars().filter(|ch| is_cell(*ch)).count();
+        match cells {
+            0 => None,
+            cells if cells >= 81 => Some(PuzzleFormat::Line),
+            _ => Some(PuzzleFormat::Grid),
+        }
+    }
+}
+
+impl fmt::Display for PuzzleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PuzzleFormat::Line => "line",
+            PuzzleFormat::Grid => "grid",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for PuzzleFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PuzzleFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s)
+            .ok_or_else(|| format!("unknown format `{}`, expected one of: line, grid", s))
+    }
+}
+
+fn is_cell(ch: char) -> bool {
+    ch == '.' || ch.is_ascii_digit()
+}
+
+/**
+ * Errors raised when reading puzzles.
+ */
+#[derive(Debug)]
+pub enum ReadError {
+    /// The input could not be read.
+    Io(io::Error),
+    /// The input is malformed. Line numbers start from 1.
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "cannot read puzzles: {}", err),
+            ReadError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+/**
+ * Iterator reading puzzles one at a time from a buffered reader, so that inputs of
+ * any size can be processed without loading them into memory.
+ *
+ * Empty lines and lines starting with `#` are skipped in both formats.
+ */
+pub struct PuzzleReader<R> {
+    reader: R,
+    format: Option<PuzzleFormat>,
+    line_no: usize,
+    buf: String,
+}
+
+impl<R: BufRead> PuzzleReader<R> {
+    /**
+     * Create a reader for the given format, or detect the format from the first
+     * puzzle when `format` is `None`.
+     */
+    pub fn new(reader: R, format: Option<PuzzleFormat>) -> Self {
+        PuzzleReader {
+            reader,
+            format,
+            line_no: 0,
+            buf: String::new(),
+        }
+    }
+
+    /**
+     * Return the format being read, once known.
+     */
+    pub fn format(&self) -> Option<PuzzleFormat> {
+        self.format
+    }
+
+    /**
+     * Return the number of the last line read, starting from 1.
+     */
+    pub fn line_number(&self) -> usize {
+        self.line_no
+    }
+
+    /**
+     * Read the next line that may hold cells into `self.buf`. Return `false` at the end
+     * of the input.
+     */
+    fn next_line(&mut self) -> io::Result<bool> {
+        loop {
+            self.buf.clear();
+            if self.reader.read_line(&mut self.buf)? == 0 {
+                return Ok(false);
+            }
+            self.line_no += 1;
+            let line = self.buf.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn parse_error(&self, message: String) -> ReadError {
+        ReadError::Parse {
+            line: self.line_no,
+            message,
+        }
+    }
+
+    fn read_puzzle(&mut self) -> Result<Option<SudokuMatrix>, ReadError> {
+        if !self.next_line()? {
+            return Ok(None);
+        }
+        let format = match self.format {
+            Some(format) => format,
+            None => {
+                let format = PuzzleFormat::detect(&self.buf)
+                    .ok_or_else(|| self.parse_error("no puzzle cells found".to_string()))?;
+                self.format = Some(format);
+                format
+            }
+        };
+        match format {
+            PuzzleFormat::Line => {
+                let cells = self
+                    .buf
+                    .trim()
+                    .split(|ch: char| ch.is_whitespace() || ch == ',' || ch == ';')
+                    .next()
+                    .unwrap_or("");
+                cells
+                    .parse()
+                    .map(Some)
+                    .map_err(|message| self.parse_error(message))
+            }
+            PuzzleFormat::Grid => {
+                let mut cells = String::new();
+                loop {
+                    cells.extend(self.buf.chars().filter(|ch| is_cell(*ch)));
+                    if cells.len() > 81 {
+                        return Err(self.parse_error(format!(
+                            "a grid has 81 cells, found {} so far",
+                            cells.len()
+                        )));
+                    }
+                    if cells.len() == 81 {
+                        break;
+                    }
+                    if !self.next_line()? {
+                        return Err(self.parse_error(format!(
+                            "incomplete grid with {} cells at the end of the input",
+                            cells.len()
+                        )));
+                    }
+                }
+                cells
+                    .parse()
+                    .map(Some)
+                    .map_err(|message| self.parse_error(message))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PuzzleReader<R> {
+    type Item = Result<SudokuMatrix, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_puzzle().transpose()
+    }
+}
+
+/**
+ * Parse all puzzles of a string.
+ */
+pub fn parse_puzzles(
+    input: &str,
+    format: Option<PuzzleFormat>,
+) -> Result<Vec<SudokuMatrix>, ReadError> {
+    PuzzleReader::new(input.as_bytes(), format).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::solve_sudoku;
+
+    fn puzzle() -> SudokuMatrix {
+        let mut mat = SudokuMatrix::new();
+        assert!(solve_sudoku(&mut mat, false));
+        for i in (0..81).step_by(2) {
+            mat.set_value(i / 9, i % 9, 0);
+        }
+        mat
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        let mat = puzzle();
+        for format in PuzzleFormat::ALL {
+            assert_eq!(format.to_string().parse::<PuzzleFormat>(), Ok(format));
+            let text = format!("{}\n\n{}\n", format.format(&mat), format.format(&mat));
+            let parsed = parse_puzzles(&text, Some(format)).unwrap();
+            assert_eq!(parsed, vec![mat.clone(), mat.clone()]);
+            assert_eq!(parse_puzzles(&text, None).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn test_line_format_extras() {
+        let line = puzzle().to_line();
+        let text = format!(
+            "# comment\n{},solution\n{} 3.5\n",
+            line,
+            line.replace('.', "0")
+        );
+        assert_eq!(parse_puzzles(&text, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut reader = PuzzleReader::new("1234\n".as_bytes(), Some(PuzzleFormat::Line));
+        assert!(matches!(
+            reader.next(),
+            Some(Err(ReadError::Parse { line: 1, .. }))
+        ));
+        let grid = PuzzleFormat::Grid.format(&puzzle());
+        let truncated: Vec<&str> = grid.lines().take(5).collect();
+        let result = parse_puzzles(&truncated.join("\n"), None);
+        assert!(matches!(result, Err(ReadError::Parse { line: 5, .. })));
+    }
+}
@@ -0,0 +1,1011 @@
+use crate::clues::{LittleKillerClue, OutsideClues, count_solutions_with_clues, solve_with_clues};
+use crate::history::{History, Move};
+use crate::logic::{Hint, find_hint};
+use crate::matrix::SudokuMatrix;
+use crate::solver::{count_solutions, find_solution};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+const SAVE_HEADER: &str = "# sudoku saved game v1";
+
+/**
+ * Reasons why a move is rejected by a `Game`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// The cell is a given of the puzzle.
+    GivenCell,
+    /// Notes cannot be edited on a cell holding a value.
+    FilledCell,
+    /// Values and notes are digits from 1 to 9.
+    InvalidDigit(u8),
+    /// The puzzle has no solution to reveal.
+    NoSolution,
+    /// The mistake limit has been reached.
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::GivenCell => write!(f, "the cell is a given of the puzzle"),
+            MoveError::FilledCell => write!(f, "the cell already holds a value"),
+            MoveError::InvalidDigit(v) => write!(f, "{} is not a digit from 1 to 9", v),
+            MoveError::NoSolution => write!(f, "the puzzle has no solution"),
+            MoveError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/**
+ * Stopwatch measuring the playing time, excluding pauses.
+ */
+#[derive(Clone, Copy, Debug)]
+struct Timer {
+    /// Time played before the current run.
+    elapsed: Duration,
+    /// Start of the current run, or `None` while paused.
+    started: Option<Instant>,
+}
+
+impl Timer {
+    fn elapsed(&self) -> Duration {
+        self.elapsed + self.started.map_or(Duration::ZERO, |start| start.elapsed())
+    }
+
+    fn pause(&mut self) {
+        self.elapsed = self.elapsed();
+        self.started = None;
+    }
+
+    fn resume(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+}
+
+/**
+ * A game of classic Sudoku, optionally with clues outside the grid, independent of any
+ * frontend.
+ *
+ * The game owns the puzzle, its solution, the values and notes entered by the player, the
+ * undo history, the playing time, the mistakes and the assists used. Cells are addressed by row and column
+ * from `0` to `8`; notes are bit masks where bit `d` is set for digit `d`.
+ */
+#[derive(Clone, Debug)]
+pub struct Game {
+    puzzle: SudokuMatrix,
+    clues: Option<OutsideClues>,
+    solution: Option<SudokuMatrix>,
+    /// Whether `solution` is the only solution, so that entries can be checked against it.
+    unique: bool,
+    board: SudokuMatrix,
+    notes: [u16; 81],
+    history: History,
+    timer: Timer,
+    mistakes: usize,
+    mistake_limit: Option<usize>,
+    assists: usize,
+}
+
+impl Game {
+    /**
+     * Start a game on a puzzle, whose filled cells are the givens. The timer starts running.
+     */
+    pub fn new(puzzle: SudokuMatrix) -> Self {
+        Game::with_clues(puzzle, None)
+    }
+
+    /**
+     * Start a game on a puzzle with clues outside the grid.
+     */
+    pub fn with_clues(puzzle: SudokuMatrix, clues: Option<OutsideClues>) -> Self {
+        let solution = match &clues {
+            Some(clues) => {
+                let mut solution = puzzle.clone();
+                solve_with_clues(&mut solution, clues).then_some(solution)
+            }
+            None => find_solution(&puzzle),
+        };
+        let unique = match &clues {
+            Some(clues) => count_solutions_with_clues(&puzzle, clues, 2) == 1,
+            None => count_solutions(&puzzle, 2) == 1,
+        };
+        Game {
+            board: puzzle.clone(),
+            puzzle,
+            clues,
+            solution,
+            unique,
+            notes: [0; 81],
+            history: History::new(),
+            timer: Timer {
+                elapsed: Duration::ZERO,
+                started: Some(Instant::now()),
+            },
+            mistakes: 0,
+            mistake_limit: None,
+            assists: 0,
+        }
+    }
+
+    /**
+     * Return the puzzle, with the givens only.
+     */
+    pub fn puzzle(&self) -> &SudokuMatrix {
+        &self.puzzle
+    }
+
+    pub fn clues(&self) -> Option<&OutsideClues> {
+        self.clues.as_ref()
+    }
+
+    /**
+     * Return the solution computed when the game started, or `None` if the puzzle has none.
+     * A puzzle with several solutions gets one of them.
+     */
+    pub fn solution(&self) -> Option<&SudokuMatrix> {
+        self.solution.as_ref()
+    }
+
+    /**
+     * Return the board: the givens and the values entered by the player.
+     */
+    pub fn board(&self) -> &SudokuMatrix {
+        &self.board
+    }
+
+    pub fn value(&self, r: usize, c: usize) -> u8 {
+        self.board.get_value(r, c)
+    }
+
+    pub fn notes(&self, r: usize, c: usize) -> u16 {
+        self.notes[r * 9 + c]
+    }
+
+    pub fn is_given(&self, r: usize, c: usize) -> bool {
+        self.puzzle.get_value(r, c) != 0
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /**
+     * Determine whether the puzzle has a unique solution, against which entries are checked.
+     */
+    pub fn has_unique_solution(&self) -> bool {
+        self.unique
+    }
+
+    /**
+     * Return the number of digits placed that differ from the unique solution. Mistakes are
+     * not taken back by undoing or clearing them, and are never counted for puzzles without
+     * a unique solution.
+     */
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    /**
+     * Determine whether a cell holds a digit placed by the player that differs from the
+     * unique solution. Nothing is wrong for puzzles without a unique solution.
+     */
+    pub fn is_wrong(&self, r: usize, c: usize) -> bool {
+        let value = self.value(r, c);
+        value != 0
+            && self.unique
+            && self
+                .solution
+                .as_ref()
+                .is_some_and(|solution| solution.get_value(r, c) != value)
+    }
+
+    /**
+     * Return the cells, as `(row, col)`, holding a wrong digit.
+     */
+    pub fn wrong_cells(&self) -> Vec<(usize, usize)> {
+        (0..81)
+            .map(|i| (i / 9, i % 9))
+            .filter(|&(r, c)| self.is_wrong(r, c))
+            .collect()
+    }
+
+    /**
+     * Return the number of assists used: revealed cells, hints and candidate fills.
+     */
+    pub fn assists(&self) -> usize {
+        self.assists
+    }
+
+    pub fn mistake_limit(&self) -> Option<usize> {
+        self.mistake_limit
+    }
+
+    /**
+     * Set the number of mistakes that ends the game, such as 3 for a "three strikes" game,
+     * or `None` to allow any number.
+     */
+    pub fn set_mistake_limit(&mut self, limit: Option<usize>) {
+        self.mistake_limit = limit;
+        if self.is_lost() {
+            self.timer.pause();
+        }
+    }
+
+    /**
+     * Determine whether the game is over because the mistake limit has been reached. No
+     * move is accepted any more.
+     */
+    pub fn is_lost(&self) -> bool {
+        self.mistake_limit
+            .is_some_and(|limit| self.mistakes >= limit)
+    }
+
+    /**
+     * Determine whether the board has no conflict, including the outside clues.
+     */
+    pub fn is_compatible(&self) -> bool {
+        self.board.is_compatible()
+            && self
+                .clues
+                .as_ref()
+                .is_none_or(|clues| clues.is_compatible_with(&self.board))
+    }
+
+    /**
+     * Determine whether the board is complete without conflict.
+     */
+    pub fn is_solved(&self) -> bool {
+        self.board.is_complete() && self.is_compatible()
+    }
+
+    /**
+     * Return the playing time, excluding pauses.
+     */
+    pub fn elapsed(&self) -> Duration {
+        self.timer.elapsed()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.timer.started.is_none()
+    }
+
+    pub fn pause(&mut self) {
+        self.timer.pause();
+    }
+
+    /**
+     * Resume the timer, unless the game is solved or lost.
+     */
+    pub fn resume(&mut self) {
+        if !self.is_solved() && !self.is_lost() {
+            self.timer.resume();
+        }
+    }
+
+    /**
+     * Run an action on the board and record the cells it changed as one move.
+     */
+    fn record_move(&mut self, action: impl FnOnce(&mut SudokuMatrix, &mut [u16; 81])) {
+        let was_solved = self.is_solved();
+        let (old_board, old_notes) = (self.board.clone(), self.notes);
+        action(&mut self.board, &mut self.notes);
+        let mv = Move::diff((&old_board, &old_notes), (&self.board, &self.notes));
+        self.history.record(mv);
+        self.update_timer(was_solved);
+    }
+
+    /**
+     * Stop the timer once the puzzle is solved, and start it again when a change leaves a
+     * solved board unsolved, such as undoing the last digit or resetting the board.
+     */
+    fn update_timer(&mut self, was_solved: bool) {
+        if self.is_solved() {
+            self.timer.pause();
+        } else if was_solved {
+            self.resume();
+        }
+    }
+
+    fn check_playing(&self) -> Result<(), MoveError> {
+        if self.is_lost() {
+            return Err(MoveError::GameOver);
+        }
+        Ok(())
+    }
+
+    fn check_editable(&self, r: usize, c: usize) -> Result<(), MoveError> {
+        self.check_playing()?;
+        if self.is_given(r, c) {
+            return Err(MoveError::GivenCell);
+        }
+        Ok(())
+    }
+
+    /**
+     * Place a digit in a cell, removing the digit from the notes of its row, column and
+     * box. The other notes of the cell are kept, to show again if the value is cleared.
+     *
+     * A digit differing from the unique solution counts as a mistake, and the timer stops
+     * if it reaches the mistake limit.
+     */
+    pub fn place(&mut self, r: usize, c: usize, value: u8) -> Result<(), MoveError> {
+        self.check_editable(r, c)?;
+        if !(1..=9).contains(&value) {
+            return Err(MoveError::InvalidDigit(value));
+        }
+        if self.value(r, c) != value
+            && self.unique
+            && let Some(solution) = &self.solution
+            && solution.get_value(r, c) != value
+        {
+            self.mistakes += 1;
+            if self.is_lost() {
+                self.timer.pause();
+            }
+        }
+        self.set_digit(r, c, value);
+        Ok(())
+    }
+
+    /**
+     * Place a digit in a cell as one move, removing the digit from the notes of its row,
+     * column and box.
+     */
+    fn set_digit(&mut self, r: usize, c: usize, value: u8) {
+        self.record_move(|board, notes| {
+            board.set_value(r, c, value);
+            for (i, cell_notes) in notes.iter_mut().enumerate() {
+                let (pr, pc) = (i / 9, i % 9);
+                if pr == r || pc == c || (pr / 3 == r / 3 && pc / 3 == c / 3) {
+                    *cell_notes &= !(1 << value);
+                }
+            }
+        });
+    }
+
+    /**
+     * Place the digit of the solution in a cell, as an assist. A cell already holding the
+     * right digit is left as is, and does not count as an assist.
+     *
+     * # Returns
+     * The digit revealed.
+     */
+    pub fn reveal(&mut self, r: usize, c: usize) -> Result<u8, MoveError> {
+        self.check_editable(r, c)?;
+        let value = self
+            .solution
+            .as_ref()
+            .ok_or(MoveError::NoSolution)?
+            .get_value(r, c);
+        if self.value(r, c) != value {
+            self.assists += 1;
+            self.set_digit(r, c, value);
+        }
+        Ok(value)
+    }
+
+    /**
+     * Find the next logical step from the board, as an assist. Games with outside clues,
+     * which the logic solver does not know, get a cell of the solution revealed instead.
+     *
+     * # Returns
+     * The hint, or `None` if the board is complete, has no solution (such as when it holds
+     * a wrong digit) or the game is lost.
+     */
+    pub fn hint(&mut self) -> Option<Hint> {
+        self.check_playing().ok()?;
+        let hint = if self.clues.is_some() {
+            let solution = self.solution.as_ref()?;
+            let idx = (0..81).find(|i| self.board.get_value(i / 9, i % 9) == 0)?;
+            if (0..81).any(|i| {
+                let v = self.board.get_value(i / 9, i % 9);
+                v != 0 && v != solution.get_value(i / 9, i % 9)
+            }) {
+                return None;
+            }
+            Hint::Reveal {
+                row: idx / 9,
+                col: idx % 9,
+                value: solution.get_value(idx / 9, idx % 9),
+            }
+        } else {
+            find_hint(&self.board)?
+        };
+        self.assists += 1;
+        Some(hint)
+    }
+
+    /**
+     * Set the notes of every empty cell to the digits not yet in its row, column or box,
+     * as one move and an assist.
+     */
+    pub fn fill_candidates(&mut self) -> Result<(), MoveError> {
+        self.check_playing()?;
+        self.assists += 1;
+        self.record_move(|board, notes| {
+            for (i, cell_notes) in notes.iter_mut().enumerate() {
+                let (r, c) = (i / 9, i % 9);
+                if board.get_value(r, c) != 0 {
+                    continue;
+                }
+                let mut candidates = 0b11_1111_1110;
+                for j in 0..81 {
+                    let (pr, pc) = (j / 9, j % 9);
+                    if pr == r || pc == c || (pr / 3 == r / 3 && pc / 3 == c / 3) {
+                        candidates &= !(1 << board.get_value(pr, pc));
+                    }
+                }
+                *cell_notes = candidates;
+            }
+        });
+        Ok(())
+    }
+
+    /**
+     * Clear the value of a cell.
+     */
+    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), MoveError> {
+        self.check_editable(r, c)?;
+        self.record_move(|board, _| board.set_value(r, c, 0));
+        Ok(())
+    }
+
+    /**
+     * Add a note to an empty cell, or remove it if present.
+     */
+    pub fn toggle_note(&mut self, r: usize, c: usize, value: u8) -> Result<(), MoveError> {
+        self.check_editable(r, c)?;
+        if self.value(r, c) != 0 {
+            return Err(MoveError::FilledCell);
+        }
+        if !(1..=9).contains(&value) {
+            return Err(MoveError::InvalidDigit(value));
+        }
+        self.record_move(|_, notes| notes[r * 9 + c] ^= 1 << value);
+        Ok(())
+    }
+
+    /**
+     * Clear the notes of an empty cell.
+     */
+    pub fn clear_notes(&mut self, r: usize, c: usize) -> Result<(), MoveError> {
+        self.check_editable(r, c)?;
+        if self.value(r, c) != 0 {
+            return Err(MoveError::FilledCell);
+        }
+        self.record_move(|_, notes| notes[r * 9 + c] = 0);
+        Ok(())
+    }
+
+    /**
+     * Clear every value and note entered by the player, as one move that can be undone.
+     */
+    pub fn reset(&mut self) -> Result<(), MoveError> {
+        self.check_playing()?;
+        let puzzle = self.puzzle.clone();
+        self.record_move(|board, notes| {
+            *board = puzzle;
+            *notes = [0; 81];
+        });
+        Ok(())
+    }
+
+    /**
+     * Fill the board with the solution, as one move that can be undone.
+     */
+    pub fn solve(&mut self) -> Result<(), MoveError> {
+        self.check_playing()?;
+        let solution = self.solution.clone().ok_or(MoveError::NoSolution)?;
+        self.record_move(|board, _| *board = solution);
+        Ok(())
+    }
+
+    /**
+     * Revert the last move.
+     *
+     * # Returns
+     * The row and column of the first cell changed, or `None` if there is nothing to undo
+     * or the game is lost.
+     */
+    pub fn undo(&mut self) -> Option<(usize, usize)> {
+        self.check_playing().ok()?;
+        let was_solved = self.is_solved();
+        let mv = self.history.undo()?;
+        mv.revert(&mut self.board, &mut self.notes);
+        let cell = mv
+            .changes
+            .first()
+            .map(|change| (change.cell / 9, change.cell % 9));
+        self.update_timer(was_solved);
+        cell
+    }
+
+    /**
+     * Apply the last undone move again.
+     *
+     * # Returns
+     * The row and column of the first cell changed, or `None` if there is nothing to redo
+     * or the game is lost.
+     */
+    pub fn redo(&mut self) -> Option<(usize, usize)> {
+        self.check_playing().ok()?;
+        let was_solved = self.is_solved();
+        let mv = self.history.redo()?;
+        mv.apply(&mut self.board, &mut self.notes);
+        let cell = mv
+            .changes
+            .first()
+            .map(|change| (change.cell / 9, change.cell % 9));
+        self.update_timer(was_solved);
+        cell
+    }
+
+    /**
+     * Write the game as text, to be read back with `from_save`.
+     *
+     * After a header line, each line holds a key and a value separated by a tab: the
+     * puzzle, the board and the notes of the cells, the playing time in milliseconds, the
+     * mistakes and their limit, the assists, the outside clues if any, the history position and one line
+     * per move of the history.
+     */
+    pub fn to_save(&self) -> String {
+        let mut lines = vec![
+            SAVE_HEADER.to_string(),
+            format!("puzzle\t{}", self.puzzle.to_line()),
+            format!("board\t{}", self.board.to_line()),
+        ];
+        let notes: Vec<String> = self.notes.iter().map(|n| n.to_string()).collect();
+        lines.push(format!("notes\t{}", notes.join(",")));
+        lines.push(format!("elapsed_ms\t{}", self.elapsed().as_millis()));
+        lines.push(format!("mistakes\t{}", self.mistakes));
+        if let Some(limit) = self.mistake_limit {
+            lines.push(format!("mistake_limit\t{}", limit));
+        }
+        lines.push(format!("assists\t{}", self.assists));
+        if let Some(clues) = &self.clues {
+            // Marks a game with clues, even if they are all empty.
+            lines.push("clues\t".to_string());
+            for clue in clues.little_killers.iter() {
+                lines.push(format!(
+                    "little_killer\t{},{},{},{}",
+                    clue.start().0,
+                    clue.start().1,
+                    clue.direction(),
+                    clue.sum()
+                ));
+            }
+            for (key, values) in clue_arrays(clues) {
+                if values.iter().any(|v| v.is_some()) {
+                    let values: Vec<String> = values
+                        .iter()
+                        .map(|v| v.map_or(".".to_string(), |v| v.to_string()))
+                        .collect();
+                    lines.push(format!("{}\t{}", key, values.join(",")));
+                }
+            }
+        }
+        lines.push(format!("position\t{}", self.history.position()));
+        for mv in self.history.moves() {
+            lines.push(format!("move\t{}", mv));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /**
+     * Restore a game written by `to_save`. The timer runs on from the saved playing time,
+     * unless the game is solved or lost.
+     *
+     * Lines with unknown keys are ignored, so that frontends can store their own fields in
+     * the same text.
+     */
+    pub fn from_save(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(SAVE_HEADER) {
+            return Err("not a saved game".to_string());
+        }
+        let mut fields = SavedFields::default();
+        for (idx, line) in lines {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            fields
+                .parse_line(line)
+                .map_err(|message| format!("line {}: {}", idx + 1, message))?;
+        }
+
+        let puzzle = fields
+            .puzzle
+            .ok_or_else(|| "the puzzle is missing".to_string())?;
+        let board = fields.board.unwrap_or_else(|| puzzle.clone());
+        for i in 0..81 {
+            let given = puzzle.get_value(i / 9, i % 9);
+            if given != 0 && board.get_value(i / 9, i % 9) != given {
+                return Err("the board does not match the givens of the puzzle".to_string());
+            }
+        }
+        // The moves are replayed by undo and redo, which must not touch the givens.
+        let changes_given = |mv: &Move| {
+            mv.changes
+                .iter()
+                .any(|change| puzzle.get_value(change.cell / 9, change.cell % 9) != 0)
+        };
+        if let Some(idx) = fields.moves.iter().position(changes_given) {
+            return Err(format!("move {} changes a given of the puzzle", idx + 1));
+        }
+        let position = fields.position.unwrap_or(fields.moves.len());
+        let history = History::from_moves(fields.moves, position)
+            .ok_or_else(|| "the history position is past the last move".to_string())?;
+
+        let mut game = Game::with_clues(puzzle, fields.clues);
+        game.board = board;
+        game.notes = fields.notes.unwrap_or([0; 81]);
+        game.history = history;
+        game.timer.elapsed = fields.elapsed;
+        game.mistakes = fields.mistakes;
+        game.mistake_limit = fields.mistake_limit;
+        game.assists = fields.assists;
+        if game.is_solved() || game.is_lost() {
+            game.timer.pause();
+        }
+        Ok(game)
+    }
+}
+
+/**
+ * Save keys of the clue arrays, in the order of `clue_arrays`.
+ */
+const CLUE_ARRAY_KEYS: [&str; 6] = [
+    "sandwich_rows",
+    "sandwich_cols",
+    "skyscraper_top",
+    "skyscraper_bottom",
+    "skyscraper_left",
+    "skyscraper_right",
+];
+
+/**
+ * Return the arrays of clues of one value per row or column, with their keys in saved games.
+ */
+fn clue_arrays(clues: &OutsideClues) -> [(&'static str, &[Option<u8>; 9]); 6] {
+    let [sandwich_rows, sandwich_cols, top, bottom, left, right] = CLUE_ARRAY_KEYS;
+    [
+        (sandwich_rows, &clues.sandwich_rows),
+        (sandwich_cols, &clues.sandwich_cols),
+        (top, &clues.skyscraper_top),
+        (bottom, &clues.skyscraper_bottom),
+        (left, &clues.skyscraper_left),
+        (right, &clues.skyscraper_right),
+    ]
+}
+
+/**
+ * Return the arrays of `clue_arrays` for writing, when a saved game is read.
+ */
+fn clue_arrays_mut(clues: &mut OutsideClues) -> [(&'static str, &mut [Option<u8>; 9]); 6] {
+    let [sandwich_rows, sandwich_cols, top, bottom, left, right] = CLUE_ARRAY_KEYS;
+    [
+        (sandwich_rows, &mut clues.sandwich_rows),
+        (sandwich_cols, &mut clues.sandwich_cols),
+        (top, &mut clues.skyscraper_top),
+        (bottom, &mut clues.skyscraper_bottom),
+        (left, &mut clues.skyscraper_left),
+        (right, &mut clues.skyscraper_right),
+    ]
+}
+
+/**
+ * Fields of a saved game, as read so far.
+ */
+#[derive(Default)]
+struct SavedFields {
+    puzzle: Option<SudokuMatrix>,
+    board: Option<SudokuMatrix>,
+    notes: Option<[u16; 81]>,
+    elapsed: Duration,
+    mistakes: usize,
+    mistake_limit: Option<usize>,
+    assists: usize,
+    clues: Option<OutsideClues>,
+    position: Option<usize>,
+    moves: Vec<Move>,
+}
+
+impl SavedFields {
+    /**
+     * Read the field of one line. Unknown keys are ignored.
+     */
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line
+            .split_once('\t')
+            .ok_or_else(|| "expected a key and a value separated by a tab".to_string())?;
+        let invalid = || format!("invalid {} `{}`", key, value);
+        match key {
+            "puzzle" => self.puzzle = Some(value.parse()?),
+            "board" => self.board = Some(value.parse()?),
+            "notes" => {
+                let values: Vec<u16> = value
+                    .split(',')
+                    .map(|n| n.parse::<u16>().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+                // Only bits 1 to 9 may be set.
+                let notes = <[u16; 81]>::try_from(values).map_err(|_| invalid())?;
+                if notes.iter().any(|n| n & !0b11_1111_1110 != 0) {
+                    return Err(invalid());
+                }
+                self.notes = Some(notes);
+            }
+            "elapsed_ms" => {
+                self.elapsed = Duration::from_millis(value.parse().map_err(|_| invalid())?)
+            }
+            "mistakes" => self.mistakes = value.parse().map_err(|_| invalid())?,
+            "mistake_limit" => self.mistake_limit = Some(value.parse().map_err(|_| invalid())?),
+            "assists" => self.assists = value.parse().map_err(|_| invalid())?,
+            "position" => self.position = Some(value.parse().map_err(|_| invalid())?),
+            "move" => self.moves.push(value.parse()?),
+            "clues" => {
+                self.clues.get_or_insert_default();
+            }
+            "little_killer" => {
+                let fields: Vec<&str> = value.split(',').collect();
+                let [r, c, direction, sum] = fields[..] else {
+                    return Err(invalid());
+                };
+                let clue = LittleKillerClue::new(
+                    (
+                        r.parse().map_err(|_| invalid())?,
+                        c.parse().map_err(|_| invalid())?,
+                    ),
+                    direction.parse()?,
+                    sum.parse().map_err(|_| invalid())?,
+                )?;
+                self.clues.get_or_insert_default().little_killers.push(clue);
+            }
+            // Unknown keys belong to the frontend, and must not turn the game into a clue game.
+            _ if !CLUE_ARRAY_KEYS.contains(&key) => {}
+            _ => {
+                let parsed: Vec<Option<u8>> = value
+                    .split(',')
+                    .map(|v| match v {
+                        "." => Ok(None),
+                        v => v.parse().map(Some).map_err(|_| invalid()),
+                    })
+                    .collect::<Result<_, _>>()?;
+                let parsed = parsed.try_into().map_err(|_| invalid())?;
+                let clues = self.clues.get_or_insert_default();
+                for (k, values) in clue_arrays_mut(clues) {
+                    if k == key {
+                        *values = parsed;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::find_solution;
+
+    fn puzzle() -> SudokuMatrix {
+        let mut mat = find_solution(&SudokuMatrix::new()).unwrap();
+        for i in (0..81).step_by(3) {
+            mat.set_value(i / 9, i % 9, 0);
+        }
+        mat
+    }
+
+    #[test]
+    fn test_moves_and_rules() {
+        let mut game = Game::new(puzzle());
+        let solution = game.solution().unwrap().clone();
+        assert_eq!(game.place(0, 1, 5), Err(MoveError::GivenCell));
+        assert_eq!(game.place(0, 0, 0), Err(MoveError::InvalidDigit(0)));
+
+        let v = solution.get_value(0, 0);
+        game.toggle_note(0, 3, v).unwrap();
+        game.toggle_note(3, 0, v).unwrap();
+        game.toggle_note(3, 3, v).unwrap();
+        game.place(0, 0, v).unwrap();
+        assert_eq!(game.toggle_note(0, 0, v), Err(MoveError::FilledCell));
+        assert_eq!((game.notes(0, 3), game.notes(3, 0)), (0, 0));
+        assert_eq!(game.notes(3, 3), 1 << v);
+
+        assert_eq!(game.undo(), Some((0, 0)));
+        assert_eq!((game.value(0, 0), game.notes(0, 3)), (0, 1 << v));
+        assert_eq!(game.redo(), Some((0, 0)));
+        assert_eq!(game.value(0, 0), v);
+        assert_eq!(game.history().position(), 4);
+
+        game.reset().unwrap();
+        assert_eq!(game.board(), game.puzzle());
+        assert_eq!(game.notes(3, 3), 0);
+        game.undo();
+        assert_eq!(game.value(0, 0), v);
+    }
+
+    #[test]
+    fn test_mistakes() {
+        let unique =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut game = Game::new(unique.parse().unwrap());
+        assert!(game.has_unique_solution());
+        game.set_mistake_limit(Some(2));
+        let v = game.solution().unwrap().get_value(0, 2);
+        let wrong = v % 9 + 1;
+        game.place(0, 2, v).unwrap();
+        game.place(0, 2, wrong).unwrap();
+        game.place(0, 2, wrong).unwrap();
+        game.undo();
+        game.clear(0, 2).unwrap();
+        assert_eq!(game.mistakes(), 1);
+        assert!(!game.is_lost());
+
+        game.place(0, 2, wrong).unwrap();
+        assert!(game.is_lost());
+        assert!(game.is_paused());
+        assert_eq!(game.place(0, 2, v), Err(MoveError::GameOver));
+        assert_eq!(game.undo(), None);
+        let loaded = Game::from_save(&game.to_save()).unwrap();
+        assert_eq!((loaded.mistakes(), loaded.is_lost()), (2, true));
+
+        let mut game = Game::new(SudokuMatrix::new());
+        assert!(!game.has_unique_solution());
+        game.place(0, 2, 1).unwrap();
+        game.place(0, 1, 1).unwrap();
+        assert_eq!(game.mistakes(), 0);
+    }
+
+    #[test]
+    fn test_wrong_cells() {
+        let unique =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut game = Game::new(unique.parse().unwrap());
+        let solution = game.solution().unwrap().clone();
+        game.place(0, 2, solution.get_value(0, 2)).unwrap();
+        game.place(0, 3, solution.get_value(0, 3) % 9 + 1).unwrap();
+        game.place(8, 0, solution.get_value(8, 0) % 9 + 1).unwrap();
+        assert!(!game.is_wrong(0, 0));
+        assert!(!game.is_wrong(0, 2));
+        assert!(game.is_wrong(0, 3));
+        assert_eq!(game.wrong_cells(), vec![(0, 3), (8, 0)]);
+
+        game.clear(0, 3).unwrap();
+        assert_eq!(game.wrong_cells(), vec![(8, 0)]);
+    }
+
+    #[test]
+    fn test_assists() {
+        let mut game = Game::new(puzzle());
+        let solution = game.solution().unwrap().clone();
+        game.toggle_note(0, 3, solution.get_value(0, 0)).unwrap();
+        assert_eq!(game.reveal(0, 0), Ok(solution.get_value(0, 0)));
+        assert_eq!(game.value(0, 0), solution.get_value(0, 0));
+        assert_eq!(game.notes(0, 3), 0);
+        assert_eq!(game.reveal(0, 0), Ok(solution.get_value(0, 0)));
+        assert_eq!(game.reveal(0, 1), Err(MoveError::GivenCell));
+        assert_eq!(game.assists(), 1);
+
+        assert!(game.hint().is_some());
+        game.fill_candidates().unwrap();
+        let notes = game.notes(0, 3);
+        assert_ne!(notes & (1 << solution.get_value(0, 3)), 0);
+        assert_eq!(notes & (1 << solution.get_value(0, 0)), 0);
+        assert_eq!(game.notes(0, 1), 0);
+        assert_eq!(game.assists(), 3);
+        game.undo();
+        assert_eq!(game.notes(0, 3), 0);
+
+        game.place(0, 3, solution.get_value(0, 3) % 9 + 1).unwrap();
+        assert!(game.hint().is_none());
+        let loaded = Game::from_save(&game.to_save()).unwrap();
+        assert_eq!(loaded.assists(), 3);
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut game = Game::new(puzzle());
+        let v = game.solution().unwrap().get_value(0, 0);
+        game.toggle_note(0, 3, v).unwrap();
+        game.toggle_note(0, 3, (v % 9) + 1).unwrap();
+        game.place(0, 0, v).unwrap();
+        game.place(0, 6, 1).unwrap();
+        game.undo();
+        game.pause();
+        let text = game.to_save();
+        let mut loaded = Game::from_save(&format!("{}frontend_field\tvalue\n", text)).unwrap();
+        assert!(loaded.clues().is_none());
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.notes(0, 3), game.notes(0, 3));
+        assert_eq!(loaded.history(), game.history());
+        // The playing time is saved in milliseconds.
+        assert!(loaded.elapsed() + Duration::from_millis(1) > game.elapsed());
+        assert!(!loaded.is_paused());
+        assert_eq!(loaded.to_save().lines().nth(1), text.lines().nth(1));
+        assert_eq!(loaded.redo(), Some((0, 6)));
+
+        let solution = game.solution().unwrap();
+        let mut clues = OutsideClues::sandwich_from_solution(solution);
+        clues.little_killers.push(
+            LittleKillerClue::new(
+                (0, 0),
+                crate::clues::DiagonalDirection::DownRight,
+                (0..9).map(|i| solution.get_value(i, i) as u32).sum(),
+            )
+            .unwrap(),
+        );
+        let game = Game::with_clues(puzzle(), Some(clues));
+        let loaded = Game::from_save(&game.to_save()).unwrap();
+        assert_eq!(loaded.clues(), game.clues());
+        let inside = game
+            .to_save()
+            .replace("little_killer\t0,0,", "little_killer\t4,4,");
+        assert!(Game::from_save(&inside).is_err());
+
+        assert!(Game::from_save("puzzle\t...").is_err());
+        let bad_board = text.replace(&game_line(&text, "board"), &".".repeat(81));
+        assert!(Game::from_save(&bad_board).unwrap_err().contains("givens"));
+        let bad_position = text.replace("position\t3", "position\t5");
+        assert!(Game::from_save(&bad_position).is_err());
+        let given = (0..81)
+            .find(|i| puzzle().get_value(i / 9, i % 9) != 0)
+            .unwrap();
+        let on_given = format!("{}move\t{}:0:0:0:2\n", text, given);
+        assert!(
+            Game::from_save(&on_given)
+                .unwrap_err()
+                .contains("changes a given")
+        );
+        let bad_digit = format!("{}move\t{}:0:0:0:1\n", text, given + 1);
+        assert!(Game::from_save(&bad_digit).is_err());
+    }
+
+    fn game_line(text: &str, key: &str) -> String {
+        text.lines()
+            .find_map(|line| line.strip_prefix(&format!("{}\t", key)))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_solve_stops_timer() {
+        let mut game = Game::new(puzzle());
+        assert!(!game.is_paused());
+        game.solve().unwrap();
+        assert!(game.is_solved());
+        assert!(game.is_paused());
+        let elapsed = game.elapsed();
+        game.resume();
+        assert_eq!(game.elapsed(), elapsed);
+
+        game.undo();
+        assert!(!game.is_paused());
+        game.redo();
+        assert!(game.is_paused());
+        game.reset().unwrap();
+        assert!(!game.is_paused());
+
+        // A manual pause is kept by moves that do not unsolve the board.
+        game.pause();
+        let (r, c) = (0..81)
+            .map(|i| (i / 9, i % 9))
+            .find(|&(r, c)| !game.is_given(r, c))
+            .unwrap();
+        game.place(r, c, game.solution().unwrap().get_value(r, c))
+            .unwrap();
+        assert!(game.is_paused());
+
+        let mut conflict = SudokuMatrix::new();
+        conflict.set_value(0, 0, 1);
+        conflict.set_value(0, 1, 1);
+        let mut game = Game::new(conflict);
+        assert_eq!(game.solution(), None);
+        assert_eq!(game.solve(), Err(MoveError::NoSolution));
+    }
+}
@@ -1,7 +1,149 @@
+use crate::clues::{OutsideClues, has_unique_solution_with_clues};
 use crate::matrix::SudokuMatrix;
-use crate::solver::{SudokuSolverState, solve_sudoku};
+use crate::rating::{Difficulty, RatedPuzzle, rate_puzzle_until};
+use crate::samurai::{
+    SAMURAI_SIZE, SamuraiMatrix, has_unique_samurai_solution, random_samurai_solution,
+};
+use crate::search::DeadlineExceeded;
+use crate::solver::{count_solutions, count_solutions_until, fill_random_solution, is_minimal};
+use crate::transform::SudokuTransform;
 use rand::prelude::*;
 use rand::seq::SliceRandom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+/**
+ * Symmetry of the clue layout of a generated puzzle.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// Clues are removed independently.
+    #[default]
+    None,
+    /// The layout is unchanged by a 180 degree rotation.
+    Rotational180,
+    /// The layout is unchanged by a 90 degree rotation.
+    Rotational90,
+    /// The layout is unchanged by mirroring left to right.
+    Horizontal,
+    /// The layout is unchanged by mirroring top to bottom.
+    Vertical,
+    /// The layout is unchanged by mirroring along the main diagonal.
+    Diagonal,
+    /// The layout is unchanged by mirroring along the anti-diagonal.
+    AntiDiagonal,
+    /// The layout is unchanged by every rotation and mirror of the square.
+    Dihedral,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::None,
+        Symmetry::Rotational180,
+        Symmetry::Rotational90,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Diagonal,
+        Symmetry::AntiDiagonal,
+        Symmetry::Dihedral,
+    ];
+
+    /**
+     * Return the cells that must be kept or removed together with `(r, c)`.
+     */
+    pub fn orbit(&self, r: usize, c: usize) -> Vec<(usize, usize)> {
+        let images: Vec<(usize, usize)> = match self {
+            Symmetry::None => vec![(r, c)],
+            Symmetry::Rotational180 => vec![(r, c), (8 - r, 8 - c)],
+            Symmetry::Rotational90 => vec![(r, c), (c, 8 - r), (8 - r, 8 - c), (8 - c, r)],
+            Symmetry::Horizontal => vec![(r, c), (r, 8 - c)],
+            Symmetry::Vertical => vec![(r, c), (8 - r, c)],
+            Symmetry::Diagonal => vec![(r, c), (c, r)],
+            Symmetry::AntiDiagonal => vec![(r, c), (8 - c, 8 - r)],
+            Symmetry::Dihedral => vec![
+                (r, c),
+                (c, 8 - r),
+                (8 - r, 8 - c),
+                (8 - c, r),
+                (r, 8 - c),
+                (8 - r, c),
+                (c, r),
+                (8 - c, 8 - r),
+            ],
+        };
+        let mut orbit = vec![];
+        for cell in images {
+            if !orbit.contains(&cell) {
+                orbit.push(cell);
+            }
+        }
+        orbit
+    }
+
+    /**
+     * Split the 81 cells into disjoint orbits.
+     */
+    pub fn orbits(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = [false; 81];
+        let mut orbits = vec![];
+        for i in 0..81 {
+            if seen[i] {
+                continue;
+            }
+            let orbit = self.orbit(i / 9, i % 9);
+            for (r, c) in orbit.iter() {
+                seen[r * 9 + c] = true;
+            }
+            orbits.push(orbit);
+        }
+        orbits
+    }
+
+    /**
+     * Determine whether the clue layout of a matrix has this symmetry.
+     */
+    pub fn matches(&self, mat: &SudokuMatrix) -> bool {
+        self.orbits().iter().all(|orbit| {
+            let filled = |(r, c): &(usize, usize)| mat.get_value(*r, *c) != 0;
+            orbit.iter().all(filled) || !orbit.iter().any(filled)
+        })
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Symmetry::None => "none",
+            Symmetry::Rotational180 => "rotational180",
+            Symmetry::Rotational90 => "rotational90",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::Diagonal => "diagonal",
+            Symmetry::AntiDiagonal => "anti-diagonal",
+            Symmetry::Dihedral => "dihedral",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Symmetry::ALL
+            .into_iter()
+            .find(|sym| sym.to_string() == s)
+            .ok_or_else(|| {
+                let names: Vec<String> = Symmetry::ALL.iter().map(|sym| sym.to_string()).collect();
+                format!(
+                    "unknown symmetry `{}`, expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
 
 /**
  * Generate a valid, solable Sudoku matrix with a specified number of filled cells.
@@ -14,53 +156,505 @@ use rand::seq::SliceRandom;
  * There must be at least one solution for this matrix.
  */
 pub fn create_matrix(filled_cnt: usize) -> SudokuMatrix {
-    loop {
-        let mut mat = create_seed_matrix(15);
-        if solve_sudoku(&mut mat, false) {
-            let mut rng = rand::rng();
-            let mut idx: Vec<usize> = (0..81).collect();
-            idx.shuffle(&mut rng);
-            for to_remove in idx.iter().take(81).skip(filled_cnt) {
-                let x = to_remove / 9;
-                let y = to_remove % 9;
-                mat.set_value(x, y, 0);
+    let mut mat = create_full_matrix();
+    let mut rng = rand::rng();
+    let mut idx: Vec<usize> = (0..81).collect();
+    idx.shuffle(&mut rng);
+    for to_remove in idx.iter().take(81).skip(filled_cnt) {
+        let x = to_remove / 9;
+        let y = to_remove % 9;
+        mat.set_value(x, y, 0);
+    }
+    mat
+}
+
+/**
+ * Generate a Sudoku puzzle with a unique solution and a symmetric clue layout.
+ *
+ * Clues are removed one orbit of the symmetry at a time, and a removal is undone if
+ * the puzzle would no longer have a unique solution.
+ *
+ * # Arguments
+ * * `min_filled_cnt` - Clues are not removed below this count.
+ * * `symmetry` - The symmetry of the clue layout.
+ *
+ * # Returns
+ * A SudokuMatrix with a unique solution and at least `min_filled_cnt` filled cells.
+ */
+pub fn create_symmetric_matrix(min_filled_cnt: usize, symmetry: Symmetry) -> SudokuMatrix {
+    let mut mat = create_full_matrix();
+    let mut rng = rand::rng();
+    let mut orbits = symmetry.orbits();
+    orbits.shuffle(&mut rng);
+    let mut filled_cnt = 81;
+    for orbit in orbits {
+        if filled_cnt - orbit.len() < min_filled_cnt {
+            continue;
+        }
+        let removed: Vec<u8> = orbit.iter().map(|(r, c)| mat.get_value(*r, *c)).collect();
+        for (r, c) in orbit.iter() {
+            mat.set_value(*r, *c, 0);
+        }
+        if count_solutions(&mat, 2) == 1 {
+            filled_cnt -= orbit.len();
+        } else {
+            for ((r, c), v) in orbit.iter().zip(removed) {
+                mat.set_value(*r, *c, v);
+            }
+        }
+    }
+    mat
+}
+
+/**
+ * Reduce a puzzle with a unique solution to a minimal one.
+ *
+ * Clues are tried in random order and each one is removed if the puzzle keeps its
+ * unique solution. A single pass is enough: a clue that could not be removed stays
+ * necessary once more clues are gone.
+ *
+ * # Returns
+ * The minimal puzzle, or `None` if `mat` does not have a unique solution.
+ */
+pub fn minimize_matrix(mat: &SudokuMatrix) -> Option<SudokuMatrix> {
+    if count_solutions(mat, 2) != 1 {
+        return None;
+    }
+    let mut mat = mat.clone();
+    let mut idx: Vec<usize> = (0..81).collect();
+    idx.shuffle(&mut rand::rng());
+    for i in idx {
+        let (x, y) = (i / 9, i % 9);
+        let v = mat.get_value(x, y);
+        if v == 0 {
+            continue;
+        }
+        mat.set_value(x, y, 0);
+        if count_solutions(&mat, 2) != 1 {
+            mat.set_value(x, y, v);
+        }
+    }
+    debug_assert!(is_minimal(&mat));
+    Some(mat)
+}
+
+/**
+ * Generate a minimal puzzle with as few clues as possible.
+ *
+ * Minimal puzzles are generated until one has at most `target_filled_cnt` clues, or
+ * until `max_attempts` puzzles were tried. Random minimal puzzles usually have 22 to
+ * 26 clues, so low targets need many attempts.
+ *
+ * # Returns
+ * The minimal puzzle with the fewest clues among the attempts.
+ */
+pub fn create_minimal_matrix(target_filled_cnt: usize, max_attempts: usize) -> SudokuMatrix {
+    let mut best: Option<SudokuMatrix> = None;
+    for _ in 0..max_attempts.max(1) {
+        let mat = minimize_matrix(&create_full_matrix()).unwrap();
+        if best
+            .as_ref()
+            .is_none_or(|b| mat.filled_count() < b.filled_count())
+        {
+            best = Some(mat);
+        }
+        if best
+            .as_ref()
+            .is_some_and(|b| b.filled_count() <= target_filled_cnt)
+        {
+            break;
+        }
+    }
+    best.unwrap()
+}
+
+/**
+ * Generate a puzzle with a unique solution and a given difficulty.
+ *
+ * All randomness comes from `rng`, so a seeded generator always gives the same puzzle.
+ * Clues are removed in random order while the puzzle stays unique and no harder than
+ * `difficulty`. New solution grids are tried until the final rating matches; if none
+ * matches after a bounded number of attempts, the closest puzzle found is returned.
+ */
+pub fn create_rated_matrix<R: Rng + ?Sized>(difficulty: Difficulty, rng: &mut R) -> RatedPuzzle {
+    create_rated_symmetric_matrix(difficulty, Symmetry::None, rng)
+}
+
+/**
+ * Generate a puzzle with a given difficulty whose clue layout has a given symmetry.
+ *
+ * Works like `create_rated_matrix`, but removes whole orbits of `symmetry` at a time.
+ */
+pub fn create_rated_symmetric_matrix<R: Rng + ?Sized>(
+    difficulty: Difficulty,
+    symmetry: Symmetry,
+    rng: &mut R,
+) -> RatedPuzzle {
+    create_rated_symmetric_matrix_until(difficulty, symmetry, rng, None)
+        .expect("there is no deadline")
+}
+
+/**
+ * Generate a puzzle like `create_rated_symmetric_matrix`, giving up once `deadline` has
+ * passed.
+ */
+pub fn create_rated_symmetric_matrix_before<R: Rng + ?Sized>(
+    difficulty: Difficulty,
+    symmetry: Symmetry,
+    rng: &mut R,
+    deadline: Instant,
+) -> Result<RatedPuzzle, DeadlineExceeded> {
+    create_rated_symmetric_matrix_until(difficulty, symmetry, rng, Some(deadline))
+}
+
+fn create_rated_symmetric_matrix_until<R: Rng + ?Sized>(
+    difficulty: Difficulty,
+    symmetry: Symmetry,
+    rng: &mut R,
+    deadline: Option<Instant>,
+) -> Result<RatedPuzzle, DeadlineExceeded> {
+    let mut best: Option<RatedPuzzle> = None;
+    for _ in 0..64 {
+        let solution = random_solution_grid(rng);
+        let mut puzzle = solution.clone();
+        let mut orbits = symmetry.orbits();
+        orbits.shuffle(rng);
+        for orbit in orbits {
+            let removed: Vec<u8> = orbit
+                .iter()
+                .map(|(r, c)| puzzle.get_value(*r, *c))
+                .collect();
+            for (r, c) in orbit.iter() {
+                puzzle.set_value(*r, *c, 0);
+            }
+            let keep_removal = if difficulty == Difficulty::Expert {
+                count_solutions_until(&puzzle, 2, deadline)? == 1
+            } else {
+                rate_puzzle_until(&puzzle, deadline)?
+                    .is_some_and(|rating| rating.difficulty <= difficulty)
+            };
+            if !keep_removal {
+                for ((r, c), v) in orbit.iter().zip(removed) {
+                    puzzle.set_value(*r, *c, v);
+                }
             }
+        }
+        let rating = rate_puzzle_until(&puzzle, deadline)?.expect("the puzzle stays unique");
+        let distance = |d: Difficulty| (d as i32 - difficulty as i32).abs();
+        if best
+            .as_ref()
+            .is_none_or(|b| distance(rating.difficulty) < distance(b.rating.difficulty))
+        {
+            best = Some(RatedPuzzle {
+                puzzle,
+                solution,
+                rating,
+            });
+        }
+        if best.as_ref().unwrap().rating.difficulty == difficulty {
+            break;
+        }
+    }
+    Ok(best.unwrap())
+}
+
+/**
+ * Reasons why a puzzle could not be generated from a clue pattern.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// The pattern has fewer clues than the 17 any unique puzzle needs.
+    TooFewClues(usize),
+    /// No solution grid giving a unique puzzle was found within the attempt budget.
+    BudgetExhausted(usize),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::TooFewClues(cnt) => write!(
+                f,
+                "the pattern has {} clues, but a unique puzzle needs at least 17",
+                cnt
+            ),
+            PatternError::BudgetExhausted(attempts) => write!(
+                f,
+                "no unique puzzle found for the pattern after {} attempts",
+                attempts
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/**
+ * Generate a puzzle whose clues are exactly the cells of a pattern.
+ *
+ * Random solution grids are tried until the givens on the pattern cells have a
+ * unique solution. Patterns with fewer clues need more attempts on average.
+ *
+ * # Arguments
+ * * `pattern` - `pattern[r * 9 + c]` is `true` if cell `(r, c)` must be a clue.
+ * * `max_attempts` - The number of solution grids to try before giving up.
+ */
+pub fn create_matrix_from_pattern(
+    pattern: &[bool; 81],
+    max_attempts: usize,
+) -> Result<SudokuMatrix, PatternError> {
+    let clue_cnt = pattern.iter().filter(|b| **b).count();
+    if clue_cnt < 17 {
+        return Err(PatternError::TooFewClues(clue_cnt));
+    }
+    for _ in 0..max_attempts {
+        let mut mat = create_full_matrix();
+        for (i, is_clue) in pattern.iter().enumerate() {
+            if !is_clue {
+                mat.set_value(i / 9, i % 9, 0);
+            }
+        }
+        if count_solutions(&mat, 2) == 1 {
+            return Ok(mat);
+        }
+    }
+    Err(PatternError::BudgetExhausted(max_attempts))
+}
+
+/**
+ * Generate a sandwich sudoku with a unique solution.
+ *
+ * Every row and column gets its sandwich clue, and the given digits are removed in
+ * random order as long as the puzzle keeps exactly one solution.
+ *
+ * # Returns
+ * The puzzle and its outside clues.
+ */
+pub fn create_sandwich_matrix() -> (SudokuMatrix, OutsideClues) {
+    let mut mat = create_full_matrix();
+    let clues = OutsideClues::sandwich_from_solution(&mat);
+    let mut rng = rand::rng();
+    let mut idx: Vec<usize> = (0..81).collect();
+    idx.shuffle(&mut rng);
+    for to_remove in idx {
+        let (x, y) = (to_remove / 9, to_remove % 9);
+        let v = mat.get_value(x, y);
+        mat.set_value(x, y, 0);
+        if has_unique_solution_with_clues(&mat, &clues, 5000) != Some(true) {
+            mat.set_value(x, y, v);
+        }
+    }
+    (mat, clues)
+}
+
+/**
+ * Generate a fully-filled valid Sudoku matrix.
+ */
+fn create_full_matrix() -> SudokuMatrix {
+    random_solution_grid(&mut rand::rng())
+}
+
+/**
+ * Generate a random, fully-filled valid Sudoku matrix.
+ *
+ * The grid is filled by a back-tracking search that tries the candidates of every
+ * step in random order, so no digit or cell is favored. Each try is bounded, and is
+ * restarted with fresh random choices if it runs out of budget. In the unlikely case
+ * that every try fails, a randomly transformed fixed grid is returned instead, so the
+ * function always terminates.
+ *
+ * Use `cell_value_chi_square` to measure how uniform the produced grids are.
+ */
+pub fn random_solution_grid<R: Rng + ?Sized>(rng: &mut R) -> SudokuMatrix {
+    for _ in 0..16 {
+        if let Some(mat) = fill_random_solution(&SudokuMatrix::new(), rng, 1000) {
             return mat;
         }
     }
+    let mut base = SudokuMatrix::new();
+    for r in 0..9 {
+        for c in 0..9 {
+            base.set_value(r, c, ((r * 3 + r / 3 + c) % 9 + 1) as u8);
+        }
+    }
+    let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    digits.shuffle(rng);
+    let mut transform = SudokuTransform::relabel(&digits);
+    for _ in 0..8 {
+        let (a, b) = (rng.random_range(0..3), rng.random_range(0..3));
+        let band = rng.random_range(0..3) * 3;
+        transform = transform
+            .then(&SudokuTransform::swap_bands(a, b))
+            .then(&SudokuTransform::swap_stacks(b, a))
+            .then(&SudokuTransform::swap_rows(band + a, band + b))
+            .then(&SudokuTransform::swap_cols(band + b, band + a));
+    }
+    transform.apply(&base)
 }
 
-fn create_seed_matrix(filled_cnt: usize) -> SudokuMatrix {
-    let mut mat = SudokuMatrix::new();
-    let mut state = SudokuSolverState::init_state_from_matrix(&mat);
+/**
+ * Generate a Samurai sudoku puzzle with a unique solution.
+ *
+ * Starting from a random full board, clues are removed in random order as long as
+ * the puzzle keeps exactly one solution. A removal whose uniqueness check takes too
+ * long is undone, which keeps the generation time bounded.
+ */
+pub fn create_samurai_matrix() -> SamuraiMatrix {
     let mut rng = rand::rng();
-    for fill_idx in 0..filled_cnt {
-        let pid = (rng.random::<u32>() as usize) % (81 - fill_idx);
-        if let Some((r, c)) = get_empty_cell_coordinate_by_count(&mat, pid) {
-            let avail_vals: Vec<u8> = state.avail_vals[r][c].iter().copied().collect();
-            if avail_vals.is_empty() {
-                continue;
-            }
-            let v = avail_vals.choose(&mut rng).unwrap();
-            mat.set_value(r, c, *v);
-            state.update_with_new_value(r, c, *v);
+    let mut mat = random_samurai_solution(&mut rng);
+    let mut idx: Vec<(usize, usize)> = (0..SAMURAI_SIZE * SAMURAI_SIZE)
+        .map(|i| (i / SAMURAI_SIZE, i % SAMURAI_SIZE))
+        .filter(|(r, c)| SamuraiMatrix::is_cell_used(*r, *c))
+        .collect();
+    idx.shuffle(&mut rng);
+    for (r, c) in idx {
+        let v = mat.get_value(r, c);
+        mat.set_value(r, c, 0);
+        if has_unique_samurai_solution(&mat, 2000) != Some(true) {
+            mat.set_value(r, c, v);
         }
     }
     mat
 }
 
-fn get_empty_cell_coordinate_by_count(mat: &SudokuMatrix, count: usize) -> Option<(usize, usize)> {
-    let mut count = count;
-    for i in 0..9 {
-        for j in 0..9 {
-            if mat.get_value(i, j) == 0 {
-                if count == 0 {
-                    return Some((i, j));
-                } else {
-                    count -= 1
-                }
+/**
+ * Measure how uniformly digits are spread over the cells of a set of grids.
+ *
+ * For every cell, the chi-square statistic of its digit counts against a uniform
+ * distribution is computed, and the mean over the 81 cells is returned. For unbiased
+ * grids the value stays close to 8, the degrees of freedom of the test; a generator
+ * that favors some digits in some cells gives a much larger value.
+ */
+pub fn cell_value_chi_square(grids: &[SudokuMatrix]) -> f64 {
+    if grids.is_empty() {
+        return 0.0;
+    }
+    let expected = grids.len() as f64 / 9.0;
+    let mut total = 0.0;
+    for r in 0..9 {
+        for c in 0..9 {
+            let mut counts = [0usize; 10];
+            for grid in grids {
+                counts[grid.get_value(r, c) as usize] += 1;
             }
+            total += counts[1..]
+                .iter()
+                .map(|cnt| (*cnt as f64 - expected).powi(2) / expected)
+                .sum::<f64>();
+        }
+    }
+    total / 81.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rating::rate_puzzle;
+
+    #[test]
+    fn test_orbits_cover_grid() {
+        for symmetry in Symmetry::ALL {
+            let orbits = symmetry.orbits();
+            assert_eq!(orbits.iter().map(|o| o.len()).sum::<usize>(), 81);
+            assert_eq!(symmetry.to_string().parse::<Symmetry>(), Ok(symmetry));
+        }
+        assert_eq!(Symmetry::None.orbits().len(), 81);
+        assert_eq!(Symmetry::Rotational180.orbits().len(), 41);
+        assert_eq!(Symmetry::Dihedral.orbit(0, 1).len(), 8);
+        assert_eq!(Symmetry::Dihedral.orbit(4, 4).len(), 1);
+    }
+
+    #[test]
+    fn test_minimize_matrix() {
+        let mat = create_symmetric_matrix(30, Symmetry::None);
+        let minimal = minimize_matrix(&mat).unwrap();
+        assert!(is_minimal(&minimal));
+        assert!(minimize_matrix(&SudokuMatrix::new()).is_none());
+        assert!(is_minimal(&create_minimal_matrix(17, 3)));
+    }
+
+    #[test]
+    fn test_create_matrix_from_pattern() {
+        let mut pattern = [false; 81];
+        for (i, is_clue) in pattern.iter_mut().enumerate() {
+            *is_clue = (i / 9 + i % 9) % 2 == 0;
+        }
+        let mat = create_matrix_from_pattern(&pattern, 100).unwrap();
+        for (i, is_clue) in pattern.iter().enumerate() {
+            assert_eq!(mat.get_value(i / 9, i % 9) != 0, *is_clue);
+        }
+        assert_eq!(count_solutions(&mat, 2), 1);
+
+        let sparse = [false; 81];
+        assert_eq!(
+            create_matrix_from_pattern(&sparse, 100),
+            Err(PatternError::TooFewClues(0))
+        );
+        pattern[..27].fill(true);
+        pattern[27..].fill(false);
+        assert_eq!(
+            create_matrix_from_pattern(&pattern, 3),
+            Err(PatternError::BudgetExhausted(3))
+        );
+    }
+
+    #[test]
+    fn test_random_solution_grid() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let grids: Vec<SudokuMatrix> = (0..270).map(|_| random_solution_grid(&mut rng)).collect();
+        for grid in grids.iter() {
+            assert!(grid.is_complete());
+            assert!(grid.is_compatible());
+        }
+        // The mean chi-square of 81 cells has a standard deviation of about 0.45.
+        let chi_square = cell_value_chi_square(&grids);
+        assert!(chi_square < 11.0, "chi-square {} is too large", chi_square);
+        let mut same_seed = rand::rngs::StdRng::seed_from_u64(7);
+        assert_eq!(random_solution_grid(&mut same_seed), grids[0]);
+    }
+
+    #[test]
+    fn test_create_rated_matrix() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium] {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(difficulty as u64);
+            let rated = create_rated_matrix(difficulty, &mut rng);
+            assert_eq!(rated.rating.difficulty, difficulty);
+            assert_eq!(rate_puzzle(&rated.puzzle), Some(rated.rating.clone()));
+            let mut solved = rated.puzzle.clone();
+            assert!(crate::solver::solve_sudoku(&mut solved, false));
+            assert_eq!(solved, rated.solution);
+        }
+    }
+
+    #[test]
+    fn test_create_rated_matrix_before_deadline() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let start = Instant::now();
+        let result = create_rated_symmetric_matrix_before(
+            Difficulty::Expert,
+            Symmetry::None,
+            &mut rng,
+            start,
+        );
+        assert_eq!(result, Err(DeadlineExceeded));
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_create_rated_symmetric_matrix() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let rated =
+            create_rated_symmetric_matrix(Difficulty::Easy, Symmetry::Rotational180, &mut rng);
+        assert_eq!(rated.rating.difficulty, Difficulty::Easy);
+        assert!(Symmetry::Rotational180.matches(&rated.puzzle));
+        assert_eq!(count_solutions(&rated.puzzle, 2), 1);
+    }
+
+    #[test]
+    fn test_create_symmetric_matrix() {
+        for symmetry in [Symmetry::Rotational180, Symmetry::Dihedral] {
+            let mat = create_symmetric_matrix(20, symmetry);
+            assert!(symmetry.matches(&mat));
+            assert_eq!(count_solutions(&mat, 2), 1);
         }
     }
-    None
 }
@@ -0,0 +1,291 @@
+use crate::matrix::SudokuMatrix;
+use std::fmt;
+use std::str::FromStr;
+
+/**
+ * Change of the value and the notes of one cell.
+ *
+ * Notes are bit masks where bit `d` is set for digit `d`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CellChange {
+    /// Index of the cell in row-major order, from `0` to `80`.
+    pub cell: usize,
+    pub old_value: u8,
+    pub new_value: u8,
+    pub old_notes: u16,
+    pub new_notes: u16,
+}
+
+/**
+ * A player action, made of the changes of every cell it touched. Placing a digit, for
+ * example, also removes the digit from the notes of the peer cells.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub changes: Vec<CellChange>,
+}
+
+impl Move {
+    /**
+     * Compute the move turning one state of the board into another.
+     *
+     * # Arguments
+     * * `before` the values and notes before the action
+     * * `after` the values and notes after the action
+     */
+    pub fn diff(before: (&SudokuMatrix, &[u16; 81]), after: (&SudokuMatrix, &[u16; 81])) -> Self {
+        let changes = (0..81)
+            .map(|cell| CellChange {
+                cell,
+                old_value: before.0.get_value(cell / 9, cell % 9),
+                new_value: after.0.get_value(cell / 9, cell % 9),
+                old_notes: before.1[cell],
+                new_notes: after.1[cell],
+            })
+            .filter(|change| {
+                change.old_value != change.new_value || change.old_notes != change.new_notes
+            })
+            .collect();
+        Move { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /**
+     * Apply the move to a board.
+     */
+    pub fn apply(&self, mat: &mut SudokuMatrix, notes: &mut [u16; 81]) {
+        for change in self.changes.iter() {
+            mat.set_value(change.cell / 9, change.cell % 9, change.new_value);
+            notes[change.cell] = change.new_notes;
+        }
+    }
+
+    /**
+     * Undo the move on a board.
+     */
+    pub fn revert(&self, mat: &mut SudokuMatrix, notes: &mut [u16; 81]) {
+        for change in self.changes.iter() {
+            mat.set_value(change.cell / 9, change.cell % 9, change.old_value);
+            notes[change.cell] = change.old_notes;
+        }
+    }
+}
+
+/**
+ * Writes the changes separated by spaces, each as
+ * `cell:old value:new value:old notes:new notes`.
+ */
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let changes: Vec<String> = self
+            .changes
+            .iter()
+            .map(|c| {
+                format!(
+                    "{}:{}:{}:{}:{}",
+                    c.cell, c.old_value, c.new_value, c.old_notes, c.new_notes
+                )
+            })
+            .collect();
+        write!(f, "{}", changes.join(" "))
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let changes = s
+            .split_whitespace()
+            .map(|change| {
+                let fields: Vec<&str> = change.split(':').collect();
+                let invalid = || format!("invalid cell change `{}`", change);
+                let [cell, old_value, new_value, old_notes, new_notes] = fields[..] else {
+                    return Err(invalid());
+                };
+                let change = CellChange {
+                    cell: cell.parse().map_err(|_| invalid())?,
+                    old_value: old_value.parse().map_err(|_| invalid())?,
+                    new_value: new_value.parse().map_err(|_| invalid())?,
+                    old_notes: old_notes.parse().map_err(|_| invalid())?,
+                    new_notes: new_notes.parse().map_err(|_| invalid())?,
+                };
+                // Only bits 1 to 9 of the notes may be set.
+                let notes = change.old_notes | change.new_notes;
+                if change.cell >= 81
+                    || change.old_value > 9
+                    || change.new_value > 9
+                    || notes & !0b11_1111_1110 != 0
+                {
+                    return Err(invalid());
+                }
+                Ok(change)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Move { changes })
+    }
+}
+
+/**
+ * Undo and redo history of the moves of a game.
+ *
+ * Moves before the position are done and can be undone; moves after it were undone and
+ * can be redone until a new move is recorded.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    moves: Vec<Move>,
+    position: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    /**
+     * Restore a history, such as one read back from a saved game.
+     *
+     * # Returns
+     * `None` if `position` is past the last move.
+     */
+    pub fn from_moves(moves: Vec<Move>, position: usize) -> Option<Self> {
+        if position > moves.len() {
+            return None;
+        }
+        Some(History { moves, position })
+    }
+
+    /**
+     * Return every move, including the undone ones after the position.
+     */
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /**
+     * Return the number of moves done.
+     */
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /**
+     * Record a done move, dropping the undone moves. Empty moves are ignored.
+     */
+    pub fn record(&mut self, mv: Move) {
+        if mv.is_empty() {
+            return;
+        }
+        self.moves.truncate(self.position);
+        self.moves.push(mv);
+        self.position += 1;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.moves.len()
+    }
+
+    /**
+     * Step back over the last done move.
+     *
+     * # Returns
+     * The move to revert, or `None` if there is nothing to undo.
+     */
+    pub fn undo(&mut self) -> Option<&Move> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.position -= 1;
+        Some(&self.moves[self.position])
+    }
+
+    /**
+     * Step forward over the last undone move.
+     *
+     * # Returns
+     * The move to apply again, or `None` if there is nothing to redo.
+     */
+    pub fn redo(&mut self) -> Option<&Move> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.position += 1;
+        Some(&self.moves[self.position - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut mat = SudokuMatrix::new();
+        let mut notes = [0u16; 81];
+        let mut history = History::new();
+        let mut play = |mat: &mut SudokuMatrix, notes: &mut [u16; 81], cell: usize, v: u8| {
+            let (old_mat, old_notes) = (mat.clone(), *notes);
+            mat.set_value(cell / 9, cell % 9, v);
+            notes[cell + 1] = 0;
+            history.record(Move::diff((&old_mat, &old_notes), (mat, notes)));
+        };
+        notes[1] = 1 << 5;
+        play(&mut mat, &mut notes, 0, 5);
+        play(&mut mat, &mut notes, 40, 7);
+        play(&mut mat, &mut notes, 40, 7);
+        assert_eq!(history.position(), 2);
+
+        history.undo().unwrap().revert(&mut mat, &mut notes);
+        history.undo().unwrap().revert(&mut mat, &mut notes);
+        assert!(history.undo().is_none());
+        assert_eq!(mat, SudokuMatrix::new());
+        assert_eq!(notes[1], 1 << 5);
+
+        history.redo().unwrap().apply(&mut mat, &mut notes);
+        assert_eq!((mat.get_value(0, 0), notes[1]), (5, 0));
+        history.record(Move::diff(
+            (&SudokuMatrix::new(), &[0; 81]),
+            (&mat, &[0; 81]),
+        ));
+        assert!(!history.can_redo());
+        assert_eq!(history.moves().len(), 2);
+    }
+
+    #[test]
+    fn test_move_round_trip() {
+        let mv = Move {
+            changes: vec![
+                CellChange {
+                    cell: 3,
+                    old_value: 0,
+                    new_value: 4,
+                    old_notes: 0b10010,
+                    new_notes: 0,
+                },
+                CellChange {
+                    cell: 80,
+                    old_value: 0,
+                    new_value: 0,
+                    old_notes: 1 << 4,
+                    new_notes: 0,
+                },
+            ],
+        };
+        assert_eq!(mv.to_string().parse::<Move>(), Ok(mv));
+        assert_eq!("".parse::<Move>(), Ok(Move::default()));
+        assert!("81:0:1:0:0".parse::<Move>().is_err());
+        assert!("1:0:1".parse::<Move>().is_err());
+        assert!("1:0:10:0:0".parse::<Move>().is_err());
+        assert!("1:0:0:0:1".parse::<Move>().is_err());
+        assert!("1:0:0:1024:0".parse::<Move>().is_err());
+    }
+}
@@ -1,7 +1,47 @@
+mod bank;
+mod bench;
+mod bulk;
+mod canonical;
+mod clues;
+mod daily;
+mod format;
+mod game;
 mod generator;
+mod history;
+mod logic;
 mod matrix;
+mod rating;
+mod samurai;
+mod search;
 mod solver;
+mod transform;
 
-pub use generator::create_matrix;
+pub use bank::{BankEntry, BankError, BankQuery, PuzzleBank};
+pub use bench::{BenchResult, PuzzleSet, SolverBackend, bundled_puzzle_sets, run_benchmark};
+pub use bulk::{BulkStats, process_in_parallel};
+pub use canonical::{canonical_hash, canonicalize, hash_canonical, is_equivalent};
+pub use clues::{
+    DiagonalDirection, LittleKillerClue, OutsideClues, count_solutions_with_clues, solve_with_clues,
+};
+pub use daily::{CalendarDate, daily_difficulty, daily_puzzle};
+pub use format::{PuzzleFormat, PuzzleReader, ReadError, parse_puzzles};
+pub use game::{Game, MoveError};
+pub use generator::{
+    PatternError, Symmetry, cell_value_chi_square, create_matrix, create_matrix_from_pattern,
+    create_minimal_matrix, create_rated_matrix, create_rated_symmetric_matrix,
+    create_rated_symmetric_matrix_before, create_samurai_matrix, create_sandwich_matrix,
+    create_symmetric_matrix, minimize_matrix, random_solution_grid,
+};
+pub use history::{CellChange, History, Move};
+pub use logic::{Hint, LogicSolver, LogicStep, Technique, find_hint, find_hint_before};
 pub use matrix::SudokuMatrix;
-pub use solver::{SudokuSolverState, solve_sudoku};
+pub use rating::{Difficulty, RatedPuzzle, Rating, rate_puzzle, rate_puzzle_before};
+pub use samurai::{
+    SAMURAI_GRID_OFFSETS, SAMURAI_SIZE, SamuraiMatrix, count_samurai_solutions, solve_samurai,
+};
+pub use search::DeadlineExceeded;
+pub use solver::{
+    SudokuSolverState, count_solutions, count_solutions_before, find_solution,
+    find_solution_before, is_minimal, solve_sudoku,
+};
+pub use transform::SudokuTransform;
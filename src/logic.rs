@@ -0,0 +1,582 @@
+use crate::matrix::SudokuMatrix;
+use crate::search::CLASSIC_UNITS as UNITS;
+use crate::search::DeadlineExceeded;
+use crate::solver::find_solution_until;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+/**
+ * Human solving techniques known to the logic solver, from the simplest to the hardest.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    /// A cell has a single candidate left.
+    NakedSingle,
+    /// A digit has a single possible place left in a row, column or box.
+    HiddenSingle,
+    /// The candidates of a digit in a box are confined to one row or column, or the
+    /// other way around.
+    LockedCandidates,
+    /// Two cells of a unit share the same two candidates.
+    NakedPair,
+    /// Two digits of a unit are confined to the same two cells.
+    HiddenPair,
+    /// The candidates of a digit in two rows (or columns) are confined to the same two columns (or rows).
+    XWing,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 6] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::LockedCandidates,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::XWing,
+    ];
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Technique::NakedSingle => "naked single",
+            Technique::HiddenSingle => "hidden single",
+            Technique::LockedCandidates => "locked candidates",
+            Technique::NakedPair => "naked pair",
+            Technique::HiddenPair => "hidden pair",
+            Technique::XWing => "X-wing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Technique {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Technique::ALL
+            .into_iter()
+            .find(|t| t.to_string() == s)
+            .ok_or_else(|| format!("unknown technique `{}`", s))
+    }
+}
+
+/**
+ * One deduction of the logic solver.
+ *
+ * A step either places digits or removes candidates. Cells are `(row, column, digit)`.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogicStep {
+    pub technique: Technique,
+    pub placements: Vec<(usize, usize, u8)>,
+    pub eliminations: Vec<(usize, usize, u8)>,
+    /// A human-readable explanation, with 1-based row and column numbers.
+    pub explanation: String,
+}
+
+fn unit_name(unit: usize) -> String {
+    match unit / 9 {
+        0 => format!("row {}", unit % 9 + 1),
+        1 => format!("column {}", unit % 9 + 1),
+        _ => format!("box {}", unit % 9 + 1),
+    }
+}
+
+fn cell_name(idx: usize) -> String {
+    format!("r{}c{}", idx / 9 + 1, idx % 9 + 1)
+}
+
+fn is_peer(a: usize, b: usize) -> bool {
+    a != b && (a / 9 == b / 9 || a % 9 == b % 9 || (a / 27 == b / 27 && a % 9 / 3 == b % 9 / 3))
+}
+
+fn digits_of(mask: u16) -> impl Iterator<Item = u8> {
+    (1u8..10u8).filter(move |v| mask & (1 << v) != 0)
+}
+
+/**
+ * Solver applying human techniques step by step, without guessing.
+ *
+ * It keeps the pencil-mark candidates of every empty cell, so elimination steps
+ * carry over to later steps.
+ */
+#[derive(Clone, Debug)]
+pub struct LogicSolver {
+    values: [u8; 81],
+    candidates: [u16; 81],
+}
+
+impl LogicSolver {
+    /**
+     * Create a solver whose candidates are all digits not excluded by the filled peers.
+     */
+    pub fn new(mat: &SudokuMatrix) -> Self {
+        let mut solver = LogicSolver {
+            values: [0; 81],
+            candidates: [0b11_1111_1110; 81],
+        };
+        for idx in 0..81 {
+            let v = mat.get_value(idx / 9, idx % 9);
+            if v != 0 {
+                solver.place(idx, v);
+            }
+        }
+        solver
+    }
+
+    /**
+     * Return the current grid, with every placement made so far.
+     */
+    pub fn matrix(&self) -> SudokuMatrix {
+        let mut mat = SudokuMatrix::new();
+        for (idx, v) in self.values.iter().enumerate() {
+            mat.set_value(idx / 9, idx % 9, *v);
+        }
+        mat
+    }
+
+    /**
+     * Return the remaining candidates of a cell. A filled cell has none.
+     */
+    pub fn candidates(&self, r: usize, c: usize) -> Vec<u8> {
+        digits_of(self.candidates[r * 9 + c]).collect()
+    }
+
+    /**
+     * Determine whether every cell is filled.
+     */
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|v| *v != 0)
+    }
+
+    fn place(&mut self, idx: usize, v: u8) {
+        self.values[idx] = v;
+        self.candidates[idx] = 0;
+        for peer in 0..81 {
+            if is_peer(idx, peer) {
+                self.candidates[peer] &= !(1 << v);
+            }
+        }
+    }
+
+    /**
+     * Apply a step returned by `next_step`.
+     */
+    pub fn apply(&mut self, step: &LogicStep) {
+        for (r, c, v) in step.placements.iter() {
+            self.place(r * 9 + c, *v);
+        }
+        for (r, c, v) in step.eliminations.iter() {
+            self.candidates[r * 9 + c] &= !(1 << v);
+        }
+    }
+
+    /**
+     * Find the next deduction, trying the simplest techniques first.
+     *
+     * Return `None` if no known technique applies.
+     */
+    pub fn next_step(&self) -> Option<LogicStep> {
+        self.find_naked_single()
+            .or_else(|| self.find_hidden_single())
+            .or_else(|| self.find_locked_candidates())
+            .or_else(|| self.find_naked_pair())
+            .or_else(|| self.find_hidden_pair())
+            .or_else(|| self.find_x_wing())
+    }
+
+    /**
+     * Apply steps until the grid is solved or no technique applies.
+     *
+     * # Returns
+     * Every step applied, in order.
+     */
+    pub fn solve(&mut self) -> Vec<LogicStep> {
+        let mut steps = vec![];
+        while !self.is_solved() {
+            let Some(step) = self.next_step() else {
+                break;
+            };
+            self.apply(&step);
+            steps.push(step);
+        }
+        steps
+    }
+
+    /**
+     * Find the next step that places a digit, applying the elimination steps needed
+     * to get there.
+     *
+     * # Returns
+     * The elimination steps followed by the placing step, or `None` if the solver gets
+     * stuck before any digit can be placed.
+     */
+    pub fn next_placement(&mut self) -> Option<Vec<LogicStep>> {
+        let mut steps = vec![];
+        loop {
+            let step = self.next_step()?;
+            self.apply(&step);
+            let is_placement = !step.placements.is_empty();
+            steps.push(step);
+            if is_placement {
+                return Some(steps);
+            }
+        }
+    }
+
+    fn find_naked_single(&self) -> Option<LogicStep> {
+        let idx = (0..81).find(|idx| self.candidates[*idx].count_ones() == 1)?;
+        let v = digits_of(self.candidates[idx]).next()?;
+        Some(LogicStep {
+            technique: Technique::NakedSingle,
+            placements: vec![(idx / 9, idx % 9, v)],
+            eliminations: vec![],
+            explanation: format!("{} can only be {}", cell_name(idx), v),
+        })
+    }
+
+    fn places_in_unit(&self, unit: usize, v: u8) -> Vec<usize> {
+        UNITS[unit]
+            .iter()
+            .copied()
+            .filter(|idx| self.candidates[*idx] & (1 << v) != 0)
+            .collect()
+    }
+
+    fn find_hidden_single(&self) -> Option<LogicStep> {
+        for unit in 0..27 {
+            for v in 1u8..10u8 {
+                let places = self.places_in_unit(unit, v);
+                if places.len() == 1 {
+                    let idx = places[0];
+                    return Some(LogicStep {
+                        technique: Technique::HiddenSingle,
+                        placements: vec![(idx / 9, idx % 9, v)],
+                        eliminations: vec![],
+                        explanation: format!(
+                            "{} is the only place for {} in {}",
+                            cell_name(idx),
+                            v,
+                            unit_name(unit)
+                        ),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn eliminate_outside(&self, unit: usize, keep: &[usize], mask: u16) -> Vec<(usize, usize, u8)> {
+        UNITS[unit]
+            .iter()
+            .filter(|idx| !keep.contains(idx))
+            .flat_map(|idx| {
+                digits_of(self.candidates[*idx] & mask).map(move |v| (idx / 9, idx % 9, v))
+            })
+            .collect()
+    }
+
+    fn find_locked_candidates(&self) -> Option<LogicStep> {
+        for unit in 0..27 {
+            for v in 1u8..10u8 {
+                let places = self.places_in_unit(unit, v);
+                if places.len() < 2 {
+                    continue;
+                }
+                // Every other unit that contains all the places of the digit.
+                for (other, other_cells) in UNITS.iter().enumerate() {
+                    if other == unit || !places.iter().all(|idx| other_cells.contains(idx)) {
+                        continue;
+                    }
+                    let eliminations = self.eliminate_outside(other, &places, 1 << v);
+                    if !eliminations.is_empty() {
+                        return Some(LogicStep {
+                            technique: Technique::LockedCandidates,
+                            placements: vec![],
+                            eliminations,
+                            explanation: format!(
+                                "{} in {} must be in {}, so it is removed from the rest of {}",
+                                v,
+                                unit_name(unit),
+                                unit_name(other),
+                                unit_name(other)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn find_naked_pair(&self) -> Option<LogicStep> {
+        for (unit, cells) in UNITS.iter().enumerate() {
+            for (i, a) in cells.iter().enumerate() {
+                let mask = self.candidates[*a];
+                if mask.count_ones() != 2 {
+                    continue;
+                }
+                for b in cells[i + 1..].iter() {
+                    if self.candidates[*b] != mask {
+                        continue;
+                    }
+                    let eliminations = self.eliminate_outside(unit, &[*a, *b], mask);
+                    if !eliminations.is_empty() {
+                        let digits: Vec<String> = digits_of(mask).map(|v| v.to_string()).collect();
+                        return Some(LogicStep {
+                            technique: Technique::NakedPair,
+                            placements: vec![],
+                            eliminations,
+                            explanation: format!(
+                                "{} and {} must hold {}, so they are removed from the rest of {}",
+                                cell_name(*a),
+                                cell_name(*b),
+                                digits.join(" and "),
+                                unit_name(unit)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn find_hidden_pair(&self) -> Option<LogicStep> {
+        for unit in 0..27 {
+            for v1 in 1u8..10u8 {
+                let places = self.places_in_unit(unit, v1);
+                if places.len() != 2 {
+                    continue;
+                }
+                for v2 in v1 + 1..10 {
+                    if self.places_in_unit(unit, v2) != places {
+                        continue;
+                    }
+                    let keep = (1u16 << v1) | (1 << v2);
+                    let eliminations: Vec<(usize, usize, u8)> = places
+                        .iter()
+                        .flat_map(|idx| {
+                            digits_of(self.candidates[*idx] & !keep)
+                                .map(move |v| (idx / 9, idx % 9, v))
+                        })
+                        .collect();
+                    if !eliminations.is_empty() {
+                        return Some(LogicStep {
+                            technique: Technique::HiddenPair,
+                            placements: vec![],
+                            eliminations,
+                            explanation: format!(
+                                "{} and {} in {} can only go in {} and {}, so other candidates are removed there",
+                                v1,
+                                v2,
+                                unit_name(unit),
+                                cell_name(places[0]),
+                                cell_name(places[1])
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn find_x_wing(&self) -> Option<LogicStep> {
+        // Base units are rows (cover units are columns), then columns (cover units are rows).
+        for (base, cover) in [(0usize, 9usize), (9, 0)] {
+            for v in 1u8..10u8 {
+                for u1 in base..base + 9 {
+                    let p1 = self.places_in_unit(u1, v);
+                    if p1.len() != 2 {
+                        continue;
+                    }
+                    let lines = |places: &[usize]| -> Vec<usize> {
+                        places
+                            .iter()
+                            .map(|idx| if cover == 9 { idx % 9 } else { idx / 9 })
+                            .collect()
+                    };
+                    for u2 in u1 + 1..base + 9 {
+                        let p2 = self.places_in_unit(u2, v);
+                        if p2.len() != 2 || lines(&p1) != lines(&p2) {
+                            continue;
+                        }
+                        let corners: Vec<usize> = p1.iter().chain(p2.iter()).copied().collect();
+                        let eliminations: Vec<(usize, usize, u8)> = lines(&p1)
+                            .iter()
+                            .flat_map(|line| self.eliminate_outside(cover + line, &corners, 1 << v))
+                            .collect();
+                        if !eliminations.is_empty() {
+                            return Some(LogicStep {
+                                technique: Technique::XWing,
+                                placements: vec![],
+                                eliminations,
+                                explanation: format!(
+                                    "{} in {} and {} is confined to {} and {}, so it is removed from the rest of them",
+                                    v,
+                                    unit_name(u1),
+                                    unit_name(u2),
+                                    unit_name(cover + lines(&p1)[0]),
+                                    unit_name(cover + lines(&p1)[1])
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/**
+ * A hint for the next move on a puzzle.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Logic steps leading to a placement, which the last step makes.
+    Logic(Vec<LogicStep>),
+    /// No known technique applies, so the value of the most constrained empty cell is
+    /// revealed from the solution.
+    Reveal { row: usize, col: usize, value: u8 },
+}
+
+/**
+ * Find a hint for the next move on a puzzle.
+ *
+ * Return `None` if the puzzle is already complete or has no solution.
+ */
+pub fn find_hint(mat: &SudokuMatrix) -> Option<Hint> {
+    find_hint_until(mat, None).expect("there is no deadline")
+}
+
+/**
+ * Find a hint like `find_hint`, giving up once `deadline` has passed.
+ */
+pub fn find_hint_before(
+    mat: &SudokuMatrix,
+    deadline: Instant,
+) -> Result<Option<Hint>, DeadlineExceeded> {
+    find_hint_until(mat, Some(deadline))
+}
+
+fn find_hint_until(
+    mat: &SudokuMatrix,
+    deadline: Option<Instant>,
+) -> Result<Option<Hint>, DeadlineExceeded> {
+    if mat.is_complete() {
+        return Ok(None);
+    }
+    let Some(solution) = find_solution_until(mat, deadline)? else {
+        return Ok(None);
+    };
+    let mut solver = LogicSolver::new(mat);
+    if let Some(steps) = solver.next_placement() {
+        return Ok(Some(Hint::Logic(steps)));
+    }
+    let solver = LogicSolver::new(mat);
+    let Some(idx) = (0..81)
+        .filter(|idx| solver.values[*idx] == 0)
+        .min_by_key(|idx| solver.candidates[*idx].count_ones())
+    else {
+        return Ok(None);
+    };
+    let (row, col) = (idx / 9, idx % 9);
+    Ok(Some(Hint::Reveal {
+        row,
+        col,
+        value: solution.get_value(row, col),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::find_solution;
+
+    fn from_line(line: &str) -> SudokuMatrix {
+        let mut mat = SudokuMatrix::new();
+        for (i, ch) in line.chars().enumerate() {
+            mat.set_value(i / 9, i % 9, ch.to_digit(10).unwrap_or(0) as u8);
+        }
+        mat
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(UNITS[0], [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(UNITS[10], [1, 10, 19, 28, 37, 46, 55, 64, 73]);
+        assert_eq!(UNITS[22], [30, 31, 32, 39, 40, 41, 48, 49, 50]);
+        assert!(is_peer(0, 20));
+        assert!(!is_peer(0, 30));
+    }
+
+    #[test]
+    fn test_singles_solve_easy_puzzle() {
+        let mat = from_line(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        );
+        let mut solver = LogicSolver::new(&mat);
+        let steps = solver.solve();
+        assert!(solver.is_solved());
+        assert!(solver.matrix().is_compatible());
+        assert!(steps.iter().all(|s| s.technique <= Technique::HiddenSingle));
+    }
+
+    #[test]
+    fn test_next_placement() {
+        let mut mat = SudokuMatrix::new();
+        for c in 0..8 {
+            mat.set_value(0, c, (c + 1) as u8);
+        }
+        let mut solver = LogicSolver::new(&mat);
+        let steps = solver.next_placement().unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].placements, vec![(0, 8, 9)]);
+        assert_eq!(steps[0].explanation, "r1c9 can only be 9");
+    }
+
+    #[test]
+    fn test_locked_candidates() {
+        let mut mat = SudokuMatrix::new();
+        // Filling the two lower rows of box 1 confines 1 to row 1 inside the box.
+        for (i, v) in (2u8..8u8).enumerate() {
+            mat.set_value(1 + i / 3, i % 3, v);
+        }
+        let solver = LogicSolver::new(&mat);
+        let step = solver.find_locked_candidates().unwrap();
+        assert_eq!(step.technique, Technique::LockedCandidates);
+        assert_eq!(step.eliminations.len(), 6);
+        assert!(
+            step.eliminations
+                .iter()
+                .all(|(r, c, v)| *r == 0 && *c >= 3 && *v == 1)
+        );
+    }
+
+    #[test]
+    fn test_find_hint() {
+        let easy = from_line(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        );
+        let Some(Hint::Logic(steps)) = find_hint(&easy) else {
+            panic!("expected a logic hint");
+        };
+        assert_eq!(steps.last().unwrap().placements.len(), 1);
+
+        // No technique applies to an empty grid.
+        let empty = SudokuMatrix::new();
+        let Some(Hint::Reveal { row, col, value }) = find_hint(&empty) else {
+            panic!("expected a revealed cell");
+        };
+        assert_eq!(find_solution(&empty).unwrap().get_value(row, col), value);
+
+        let mut conflict = SudokuMatrix::new();
+        conflict.set_value(0, 0, 1);
+        conflict.set_value(0, 1, 1);
+        assert_eq!(find_hint(&conflict), None);
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Debug, Default)]
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 /**
  * Data class to host a sudoku matrix.
  */
@@ -75,6 +77,25 @@ impl SudokuMatrix {
         println!("*");
     }
 
+    /**
+     * Return the matrix as a single line of 81 characters in row-major order, with `.` for
+     * empty cells.
+     */
+    pub fn to_line(&self) -> String {
+        self.matrix
+            .iter()
+            .flatten()
+            .map(|v| if *v == 0 { '.' } else { (b'0' + v) as char })
+            .collect()
+    }
+
+    /**
+     * Return the number of filled cells.
+     */
+    pub fn filled_count(&self) -> usize {
+        self.matrix.iter().flatten().filter(|v| **v != 0).count()
+    }
+
     /**
      * Determine whether the matrix is fully filled.
      */
@@ -118,6 +139,34 @@ impl SudokuMatrix {
     }
 }
 
+/**
+ * Parse a matrix from 81 cells in row-major order, where `1` to `9` are given digits and
+ * `.` or `0` are empty cells. Whitespace is ignored, so the cells may be split into lines.
+ */
+impl FromStr for SudokuMatrix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mat = SudokuMatrix::new();
+        let mut count = 0;
+        for ch in s.chars().filter(|ch| !ch.is_whitespace()) {
+            let v = match ch {
+                '.' | '0' => 0,
+                '1'..='9' => ch as u8 - b'0',
+                _ => return Err(format!("invalid cell `{}`", ch)),
+            };
+            if count < 81 {
+                mat.matrix[count / 9][count % 9] = v;
+            }
+            count += 1;
+        }
+        if count != 81 {
+            return Err(format!("expected 81 cells, found {}", count));
+        }
+        Ok(mat)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -125,7 +174,7 @@ mod test {
     #[test]
     fn test_new() {
         let m = SudokuMatrix::new();
-        assert!(m.is_complete() == false);
+        assert!(!m.is_complete());
         assert!(m.is_compatible());
     }
 
@@ -188,4 +237,17 @@ mod test {
         m.set_value(2, 2, 9);
         assert!(m.is_compatible());
     }
+
+    #[test]
+    fn test_line_round_trip() {
+        let mut m = SudokuMatrix::new();
+        m.set_value(0, 0, 5);
+        m.set_value(8, 8, 9);
+        let line = m.to_line();
+        assert_eq!(line.len(), 81);
+        assert!(line.starts_with("5."));
+        assert_eq!(line.parse::<SudokuMatrix>(), Ok(m));
+        assert!("123".parse::<SudokuMatrix>().is_err());
+        assert!("x".repeat(81).parse::<SudokuMatrix>().is_err());
+    }
 }
@@ -0,0 +1,165 @@
+use crate::logic::{LogicSolver, Technique};
+use crate::matrix::SudokuMatrix;
+use crate::search::DeadlineExceeded;
+use crate::solver::count_solutions_until;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+/**
+ * Difficulty grade of a puzzle, from the hardest technique its logical solution needs.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// Naked and hidden singles are enough.
+    #[default]
+    Easy,
+    /// Locked candidates are needed.
+    Medium,
+    /// Pairs or X-wings are needed.
+    Hard,
+    /// The known techniques are not enough, so some trial and error is needed.
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    fn of_technique(technique: Technique) -> Self {
+        match technique {
+            Technique::NakedSingle | Technique::HiddenSingle => Difficulty::Easy,
+            Technique::LockedCandidates => Difficulty::Medium,
+            Technique::NakedPair | Technique::HiddenPair | Technique::XWing => Difficulty::Hard,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|d| d.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown difficulty `{}`, expected one of: easy, medium, hard, expert",
+                    s
+                )
+            })
+    }
+}
+
+/**
+ * Result of rating a puzzle.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rating {
+    pub difficulty: Difficulty,
+    /// The hardest technique used, or `None` if the puzzle was already solved.
+    pub hardest_technique: Option<Technique>,
+    /// The number of logic steps applied.
+    pub steps: usize,
+    /// The number of given cells.
+    pub clue_count: usize,
+}
+
+/**
+ * Rate the difficulty of a puzzle by solving it with human techniques.
+ *
+ * Return `None` if the puzzle does not have a unique solution.
+ */
+pub fn rate_puzzle(mat: &SudokuMatrix) -> Option<Rating> {
+    rate_puzzle_until(mat, None).expect("there is no deadline")
+}
+
+/**
+ * Rate a puzzle like `rate_puzzle`, giving up once `deadline` has passed.
+ */
+pub fn rate_puzzle_before(
+    mat: &SudokuMatrix,
+    deadline: Instant,
+) -> Result<Option<Rating>, DeadlineExceeded> {
+    rate_puzzle_until(mat, Some(deadline))
+}
+
+/**
+ * `rate_puzzle` with an optional deadline.
+ */
+pub(crate) fn rate_puzzle_until(
+    mat: &SudokuMatrix,
+    deadline: Option<Instant>,
+) -> Result<Option<Rating>, DeadlineExceeded> {
+    if count_solutions_until(mat, 2, deadline)? != 1 {
+        return Ok(None);
+    }
+    let mut solver = LogicSolver::new(mat);
+    let steps = solver.solve();
+    let hardest_technique = steps.iter().map(|step| step.technique).max();
+    let difficulty = if !solver.is_solved() {
+        Difficulty::Expert
+    } else {
+        hardest_technique.map_or(Difficulty::Easy, Difficulty::of_technique)
+    };
+    Ok(Some(Rating {
+        difficulty,
+        hardest_technique,
+        steps: steps.len(),
+        clue_count: mat.filled_count(),
+    }))
+}
+
+/**
+ * A generated puzzle together with its solution and rating.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RatedPuzzle {
+    pub puzzle: SudokuMatrix,
+    pub solution: SudokuMatrix,
+    pub rating: Rating,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_puzzle() {
+        let mut mat = SudokuMatrix::new();
+        assert!(rate_puzzle(&mat).is_none());
+        assert!(crate::solver::solve_sudoku(&mut mat, false));
+        let rating = rate_puzzle(&mat).unwrap();
+        assert_eq!(rating.difficulty, Difficulty::Easy);
+        assert_eq!(rating.hardest_technique, None);
+        assert_eq!(rating.clue_count, 81);
+        mat.set_value(0, 0, 0);
+        let rating = rate_puzzle(&mat).unwrap();
+        assert_eq!(rating.hardest_technique, Some(Technique::NakedSingle));
+        assert_eq!(rating.steps, 1);
+    }
+
+    #[test]
+    fn test_difficulty_names() {
+        for d in Difficulty::ALL {
+            assert_eq!(d.to_string().parse::<Difficulty>(), Ok(d));
+        }
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+}
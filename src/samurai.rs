@@ -0,0 +1,342 @@
+use crate::matrix::SudokuMatrix;
+use crate::search::{CLASSIC_UNITS, Constraints, Search};
+use rand::prelude::*;
+use std::sync::LazyLock;
+
+/**
+ * Side length of the square board that hosts a Samurai sudoku.
+ */
+pub const SAMURAI_SIZE: usize = 21;
+
+/**
+ * Top-left coordinates of the five 9x9 sub-grids of a Samurai sudoku.
+ *
+ * The sub-grid at index 2 is the center grid, whose four corner boxes are
+ * shared with the corner boxes of the other four sub-grids.
+ */
+pub const SAMURAI_GRID_OFFSETS: [(usize, usize); 5] = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+
+/**
+ * For every board cell, a bitmask of the sub-grids containing it.
+ */
+const CELL_GRIDS: [[u8; SAMURAI_SIZE]; SAMURAI_SIZE] = {
+    let mut table = [[0u8; SAMURAI_SIZE]; SAMURAI_SIZE];
+    let mut g = 0;
+    while g < SAMURAI_GRID_OFFSETS.len() {
+        let (gr, gc) = SAMURAI_GRID_OFFSETS[g];
+        let mut idx = 0;
+        while idx < 81 {
+            table[gr + idx / 9][gc + idx % 9] |= 1 << g;
+            idx += 1;
+        }
+        g += 1;
+    }
+    table
+};
+
+#[derive(Clone, Debug, Default)]
+/**
+ * Data class to host a Samurai sudoku: five overlapping 9x9 grids placed on a 21x21 board.
+ *
+ * Cells of the board that do not belong to any sub-grid are never used and always hold `0`.
+ */
+pub struct SamuraiMatrix {
+    matrix: [[u8; SAMURAI_SIZE]; SAMURAI_SIZE],
+}
+
+impl SamuraiMatrix {
+    /**
+     * Creates a new empty Samurai matrix.
+     */
+    pub fn new() -> Self {
+        SamuraiMatrix {
+            matrix: [[0u8; SAMURAI_SIZE]; SAMURAI_SIZE],
+        }
+    }
+
+    /**
+     * Determine whether a cell of the 21x21 board belongs to at least one sub-grid.
+     */
+    pub fn is_cell_used(r: usize, c: usize) -> bool {
+        CELL_GRIDS[r][c] != 0
+    }
+
+    /**
+     * Iterate over the sub-grids containing a cell.
+     *
+     * Each item is `(grid_idx, local_row, local_col)`. Cells in the shared corner boxes
+     * belong to two sub-grids.
+     */
+    pub fn grids_of_cell(r: usize, c: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+        let grids = if r < SAMURAI_SIZE && c < SAMURAI_SIZE {
+            CELL_GRIDS[r][c]
+        } else {
+            0
+        };
+        SAMURAI_GRID_OFFSETS
+            .iter()
+            .enumerate()
+            .filter(move |(g, _)| grids & (1 << g) != 0)
+            .map(move |(g, (gr, gc))| (g, r - gr, c - gc))
+    }
+
+    /**
+     * Set value on a cell. A value of `0` will clear the cell.
+     *
+     * Setting a value on an unused cell has no effect.
+     *
+     * # Arguments
+     * * `r` row on the 21x21 board
+     * * `c` column on the 21x21 board
+     * * `v` the value to be set
+     */
+    pub fn set_value(&mut self, r: usize, c: usize, v: u8) {
+        if Self::is_cell_used(r, c) {
+            self.matrix[r][c] = v;
+        }
+    }
+
+    /**
+     * Get the value on a cell. A value of `0` means the cell is not filled or not used.
+     *
+     * # Arguments
+     * * `r` row on the 21x21 board
+     * * `c` column on the 21x21 board
+     */
+    pub fn get_value(&self, r: usize, c: usize) -> u8 {
+        self.matrix[r][c]
+    }
+
+    /**
+     * Copy one of the five sub-grids out as a classic Sudoku matrix.
+     *
+     * # Arguments
+     * * `grid_idx` index into `SAMURAI_GRID_OFFSETS`
+     */
+    pub fn sub_grid(&self, grid_idx: usize) -> SudokuMatrix {
+        let (gr, gc) = SAMURAI_GRID_OFFSETS[grid_idx];
+        let mut mat = SudokuMatrix::new();
+        for r in 0..9 {
+            for c in 0..9 {
+                mat.set_value(r, c, self.matrix[gr + r][gc + c]);
+            }
+        }
+        mat
+    }
+
+    /**
+     * Return the number of filled cells.
+     */
+    pub fn filled_count(&self) -> usize {
+        self.matrix.iter().flatten().filter(|v| **v != 0).count()
+    }
+
+    pub fn print(&self) {
+        for r in 0..SAMURAI_SIZE {
+            if r % 3 == 0 {
+                println!();
+            }
+            for c in 0..SAMURAI_SIZE {
+                if c % 3 == 0 {
+                    print!(" ");
+                }
+                if !Self::is_cell_used(r, c) {
+                    print!("  ");
+                } else if self.matrix[r][c] == 0 {
+                    print!(" .");
+                } else {
+                    print!(" {}", self.matrix[r][c]);
+                }
+            }
+            println!();
+        }
+    }
+
+    /**
+     * Determine whether every used cell is filled.
+     */
+    pub fn is_complete(&self) -> bool {
+        for r in 0..SAMURAI_SIZE {
+            for c in 0..SAMURAI_SIZE {
+                if Self::is_cell_used(r, c) && self.matrix[r][c] == 0 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /**
+     * Determine whether there is a conflict in any of the sub-grids. Return `true` if there is no conflict.
+     */
+    pub fn is_compatible(&self) -> bool {
+        (0..SAMURAI_GRID_OFFSETS.len()).all(|g| self.sub_grid(g).is_compatible())
+    }
+}
+
+/**
+ * The rules of a Samurai board for `Search`: 27 units per sub-grid, so placing a digit on
+ * a shared cell propagates it to both sub-grids the cell belongs to.
+ *
+ * Cells are numbered `r * SAMURAI_SIZE + c`, and the units of sub-grid `g` are
+ * `27 * g..27 * (g + 1)`: rows, then columns, then boxes.
+ */
+struct SamuraiGrid {
+    units: Vec<[usize; 9]>,
+    cell_units: Vec<Vec<usize>>,
+}
+
+static SAMURAI_GRID: LazyLock<SamuraiGrid> = LazyLock::new(|| {
+    let mut units = vec![];
+    let mut cell_units = vec![vec![]; SAMURAI_SIZE * SAMURAI_SIZE];
+    for (gr, gc) in SAMURAI_GRID_OFFSETS {
+        for cells in CLASSIC_UNITS {
+            let cells = cells.map(|cell| (gr + cell / 9) * SAMURAI_SIZE + gc + cell % 9);
+            for cell in cells {
+                cell_units[cell].push(units.len());
+            }
+            units.push(cells);
+        }
+    }
+    SamuraiGrid { units, cell_units }
+});
+
+impl Constraints for SamuraiGrid {
+    fn cell_count(&self) -> usize {
+        SAMURAI_SIZE * SAMURAI_SIZE
+    }
+
+    fn units(&self) -> &[[usize; 9]] {
+        &self.units
+    }
+
+    fn units_of(&self, cell: usize) -> &[usize] {
+        &self.cell_units[cell]
+    }
+}
+
+fn new_search(mat: &SamuraiMatrix) -> Option<Search<'static, SamuraiGrid>> {
+    Search::new(&SAMURAI_GRID, mat.matrix.as_flattened())
+}
+
+fn cells_matrix(cells: &[u8]) -> SamuraiMatrix {
+    let mut mat = SamuraiMatrix::new();
+    for (row, values) in mat.matrix.iter_mut().zip(cells.chunks(SAMURAI_SIZE)) {
+        row.copy_from_slice(values);
+    }
+    mat
+}
+
+/**
+ * Solve a partially-filled Samurai sudoku.
+ *
+ * Return true on success, false on failure. On failure the matrix is left untouched.
+ */
+pub fn solve_samurai(mat: &mut SamuraiMatrix) -> bool {
+    let Some(mut search) = new_search(mat) else {
+        return false;
+    };
+    search.run::<ThreadRng>(1, None);
+    match search.solution() {
+        Some(solved) => {
+            *mat = cells_matrix(solved);
+            true
+        }
+        None => false,
+    }
+}
+
+/**
+ * Count the solutions of a Samurai sudoku, stopping once `limit` solutions were found.
+ */
+pub fn count_samurai_solutions(mat: &SamuraiMatrix, limit: usize) -> usize {
+    let Some(mut search) = new_search(mat) else {
+        return 0;
+    };
+    search.run::<ThreadRng>(limit, None)
+}
+
+/**
+ * Check that a Samurai sudoku has exactly one solution, giving up after `max_nodes` search nodes.
+ *
+ * Return `None` when the budget ran out before the answer was known.
+ */
+pub(crate) fn has_unique_samurai_solution(mat: &SamuraiMatrix, max_nodes: usize) -> Option<bool> {
+    let mut search = new_search(mat)?;
+    search.max_nodes = max_nodes;
+    let found = search.run::<ThreadRng>(2, None);
+    if search.is_exhausted() {
+        None
+    } else {
+        Some(found == 1)
+    }
+}
+
+/**
+ * Fill an empty Samurai board with a random valid solution.
+ *
+ * The center grid is filled first. Each corner grid then only has to be completed
+ * around the box it shares with the center, which always succeeds, so the search
+ * never has to backtrack across sub-grids.
+ */
+pub(crate) fn random_samurai_solution<R: Rng>(rng: &mut R) -> SamuraiMatrix {
+    let mut mat = SamuraiMatrix::new();
+    for g in [2, 0, 1, 3, 4] {
+        let mut search = new_search(&mat).unwrap();
+        search.set_scope(27 * g..27 * (g + 1));
+        search.run(1, Some(&mut *rng));
+        mat = cells_matrix(
+            search
+                .solution()
+                .expect("a sub-grid with one given box always has a solution"),
+        );
+    }
+    mat
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_used_cells() {
+        assert!(SamuraiMatrix::is_cell_used(0, 0));
+        assert!(SamuraiMatrix::is_cell_used(10, 10));
+        assert!(!SamuraiMatrix::is_cell_used(0, 10));
+        assert!(!SamuraiMatrix::is_cell_used(10, 0));
+        assert_eq!(SamuraiMatrix::grids_of_cell(7, 7).count(), 2);
+        assert_eq!(SamuraiMatrix::grids_of_cell(10, 10).count(), 1);
+    }
+
+    #[test]
+    fn test_shared_cell_conflict() {
+        let mut m = SamuraiMatrix::new();
+        // (6, 6) is shared by the top-left and center grids.
+        m.set_value(6, 6, 5);
+        m.set_value(6, 12, 5);
+        assert!(!m.is_compatible());
+        let mut m = SamuraiMatrix::new();
+        m.set_value(6, 6, 5);
+        m.set_value(0, 6, 5);
+        assert!(!m.is_compatible());
+    }
+
+    #[test]
+    fn test_solve_empty() {
+        let mut m = SamuraiMatrix::new();
+        assert!(solve_samurai(&mut m));
+        assert!(m.is_complete());
+        assert!(m.is_compatible());
+    }
+
+    #[test]
+    fn test_solution_is_unique_when_full() {
+        let m = random_samurai_solution(&mut rand::rng());
+        assert!(m.is_complete());
+        assert!(m.is_compatible());
+        assert_eq!(count_samurai_solutions(&m, 2), 1);
+        let mut removed = m.clone();
+        removed.set_value(8, 8, 0);
+        assert_eq!(count_samurai_solutions(&removed, 2), 1);
+    }
+}
@@ -0,0 +1,381 @@
+use rand::prelude::*;
+use std::fmt;
+use std::ops::Range;
+use std::time::Instant;
+
+/**
+ * Number of search nodes between two checks of the deadline of a `Search`.
+ */
+const DEADLINE_CHECK_NODES: usize = 1024;
+
+/**
+ * Error returned by the `*_before` functions when their deadline passes before they finish.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeadlineExceeded;
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the deadline passed before the search finished")
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/**
+ * Cell indices of the 27 units of a classic grid: rows `0..9`, columns `9..18` and boxes
+ * `18..27`, with cells numbered in row-major order.
+ */
+pub(crate) const CLASSIC_UNITS: [[usize; 9]; 27] = {
+    let mut units = [[0usize; 9]; 27];
+    let mut i = 0;
+    while i < 9 {
+        let mut j = 0;
+        while j < 9 {
+            units[i][j] = i * 9 + j;
+            units[9 + i][j] = j * 9 + i;
+            units[18 + i][j] = (i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3;
+            j += 1;
+        }
+        i += 1;
+    }
+    units
+};
+
+/**
+ * The row, column and box of every cell of a classic grid.
+ */
+const CLASSIC_CELL_UNITS: [[usize; 3]; 81] = {
+    let mut table = [[0usize; 3]; 81];
+    let mut cell = 0;
+    while cell < 81 {
+        let (r, c) = (cell / 9, cell % 9);
+        table[cell] = [r, 9 + c, 18 + r / 3 * 3 + c / 3];
+        cell += 1;
+    }
+    table
+};
+
+/**
+ * The rules searched by `Search`: cells grouped into units of nine cells, which each hold
+ * every digit once, and optionally extra rules checked on every placement.
+ */
+pub(crate) trait Constraints {
+    /// Number of cells, including the ones that belong to no unit and are never filled.
+    fn cell_count(&self) -> usize;
+
+    /// Every unit, as the indices of its nine cells.
+    fn units(&self) -> &[[usize; 9]];
+
+    /// The units a cell belongs to.
+    fn units_of(&self, cell: usize) -> &[usize];
+
+    /**
+     * Determine whether the digit just placed on `cell` keeps the extra rules satisfiable.
+     */
+    fn allows(&self, _values: &[u8], _cell: usize) -> bool {
+        true
+    }
+}
+
+/**
+ * The rules of a classic 9x9 grid.
+ */
+pub(crate) struct ClassicGrid;
+
+impl Constraints for ClassicGrid {
+    fn cell_count(&self) -> usize {
+        81
+    }
+
+    fn units(&self) -> &[[usize; 9]] {
+        &CLASSIC_UNITS
+    }
+
+    fn units_of(&self, cell: usize) -> &[usize] {
+        &CLASSIC_CELL_UNITS[cell]
+    }
+}
+
+/**
+ * Bitmask back-tracking search over any grid made of units, shared by the classic,
+ * Samurai and outside-clue solvers.
+ *
+ * Branches on the empty cell with the fewest candidates, or on the digit of a unit with
+ * the fewest possible places when that is smaller.
+ */
+pub(crate) struct Search<'a, C: Constraints> {
+    constraints: &'a C,
+    values: Vec<u8>,
+    /// Digits placed in every unit.
+    masks: Vec<u16>,
+    /// Units searched; cells belonging to none of them are left as they are.
+    scope: Range<usize>,
+    in_scope: Vec<bool>,
+    /// The first solution found.
+    solution: Option<Vec<u8>>,
+    /// Number of search nodes visited so far.
+    pub nodes: usize,
+    /// Search is abandoned once `nodes` exceeds this budget.
+    pub max_nodes: usize,
+    /// Search is abandoned once this instant has passed.
+    pub deadline: Option<Instant>,
+    /// Whether the search was abandoned at the deadline.
+    timed_out: bool,
+}
+
+impl<'a, C: Constraints> Search<'a, C> {
+    /**
+     * Start a search from the given values, one per cell.
+     *
+     * Return `None` if two givens conflict, a given is not a digit, or the givens break
+     * the extra rules.
+     */
+    pub fn new(constraints: &'a C, givens: &[u8]) -> Option<Self> {
+        let cell_count = constraints.cell_count();
+        let mut search = Search {
+            constraints,
+            values: vec![0; cell_count],
+            masks: vec![0; constraints.units().len()],
+            scope: 0..constraints.units().len(),
+            in_scope: vec![false; cell_count],
+            solution: None,
+            nodes: 0,
+            max_nodes: usize::MAX,
+            deadline: None,
+            timed_out: false,
+        };
+        search.set_scope(search.scope.clone());
+        for (cell, v) in givens.iter().enumerate() {
+            if *v != 0 {
+                if *v > 9 || search.candidates(cell) & (1 << v) == 0 {
+                    return None;
+                }
+                search.place(cell, *v);
+            }
+        }
+        (0..cell_count)
+            .filter(|cell| search.values[*cell] != 0)
+            .all(|cell| constraints.allows(&search.values, cell))
+            .then_some(search)
+    }
+
+    /**
+     * Restrict the search to a range of units.
+     */
+    pub fn set_scope(&mut self, scope: Range<usize>) {
+        for cell in 0..self.values.len() {
+            self.in_scope[cell] = self
+                .constraints
+                .units_of(cell)
+                .iter()
+                .any(|unit| scope.contains(unit));
+        }
+        self.scope = scope;
+    }
+
+    /**
+     * Return the first solution found, one value per cell.
+     */
+    pub fn solution(&self) -> Option<&[u8]> {
+        self.solution.as_deref()
+    }
+
+    /**
+     * Determine whether the search ran out of its node budget or passed its deadline.
+     */
+    pub fn is_exhausted(&self) -> bool {
+        self.nodes > self.max_nodes || self.timed_out
+    }
+
+    /**
+     * Fail if the search was abandoned at its deadline, in which case its results are
+     * incomplete.
+     */
+    pub fn check_deadline(&self) -> Result<(), DeadlineExceeded> {
+        if self.timed_out {
+            Err(DeadlineExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn candidates(&self, cell: usize) -> u16 {
+        let placed = self
+            .constraints
+            .units_of(cell)
+            .iter()
+            .fold(0, |mask, unit| mask | self.masks[*unit]);
+        !placed & 0b11_1111_1110
+    }
+
+    fn place(&mut self, cell: usize, v: u8) {
+        self.values[cell] = v;
+        for unit in self.constraints.units_of(cell) {
+            self.masks[*unit] |= 1 << v;
+        }
+    }
+
+    fn unplace(&mut self, cell: usize) {
+        let v = self.values[cell];
+        self.values[cell] = 0;
+        for unit in self.constraints.units_of(cell) {
+            self.masks[*unit] &= !(1 << v);
+        }
+    }
+
+    /**
+     * Return the choices to branch on, `Some(vec![])` on a dead end, or `None` when solved.
+     */
+    fn next_choices(&self) -> Option<Vec<(usize, u8)>> {
+        let mut cands = vec![0u16; self.values.len()];
+        let mut best: Option<(usize, u16)> = None;
+        for (cell, cell_cands) in cands.iter_mut().enumerate() {
+            if self.values[cell] != 0 || !self.in_scope[cell] {
+                continue;
+            }
+            let avail = self.candidates(cell);
+            if avail == 0 {
+                return Some(vec![]);
+            }
+            *cell_cands = avail;
+            if best.is_none_or(|(_, b)| avail.count_ones() < b.count_ones()) {
+                best = Some((cell, avail));
+            }
+        }
+        let (cell, avail) = best?;
+        let mut best_cnt = avail.count_ones() as usize;
+        let mut best_unit: Option<(usize, u8)> = None;
+        if best_cnt > 1 {
+            'scan: for unit in self.scope.clone() {
+                let cells = &self.constraints.units()[unit];
+                for v in 1u8..10u8 {
+                    if self.masks[unit] & (1 << v) != 0 {
+                        continue;
+                    }
+                    let cnt = cells
+                        .iter()
+                        .filter(|cell| cands[**cell] & (1 << v) != 0)
+                        .count();
+                    if cnt == 0 {
+                        return Some(vec![]);
+                    }
+                    if cnt < best_cnt {
+                        best_cnt = cnt;
+                        best_unit = Some((unit, v));
+                        if cnt == 1 {
+                            break 'scan;
+                        }
+                    }
+                }
+            }
+        }
+        Some(match best_unit {
+            Some((unit, v)) => self.constraints.units()[unit]
+                .iter()
+                .filter(|cell| cands[**cell] & (1 << v) != 0)
+                .map(|cell| (*cell, v))
+                .collect(),
+            None => (1u8..10u8)
+                .filter(|v| avail & (1 << v) != 0)
+                .map(|v| (cell, v))
+                .collect(),
+        })
+    }
+
+    /**
+     * Depth-first search, stopping once `limit` solutions were counted or the node budget
+     * ran out. The first solution found is kept. When `rng` is given, choices are tried in
+     * random order.
+     *
+     * # Returns
+     * The number of solutions found.
+     */
+    pub fn run<R: Rng + ?Sized>(&mut self, limit: usize, rng: Option<&mut R>) -> usize {
+        let mut found = 0;
+        self.search(limit, &mut found, rng);
+        found
+    }
+
+    fn search<R: Rng + ?Sized>(
+        &mut self,
+        limit: usize,
+        found: &mut usize,
+        mut rng: Option<&mut R>,
+    ) {
+        self.nodes += 1;
+        // The clock is read on the first node, so that a search started late stops at once.
+        if let Some(deadline) = self.deadline
+            && self.nodes % DEADLINE_CHECK_NODES == 1
+            && Instant::now() >= deadline
+        {
+            self.timed_out = true;
+        }
+        if self.is_exhausted() {
+            return;
+        }
+        let Some(mut choices) = self.next_choices() else {
+            *found += 1;
+            if self.solution.is_none() {
+                self.solution = Some(self.values.clone());
+            }
+            return;
+        };
+        if let Some(rng) = rng.as_deref_mut() {
+            choices.shuffle(rng);
+        }
+        for (cell, v) in choices {
+            self.place(cell, v);
+            if self.constraints.allows(&self.values, cell) {
+                self.search(limit, found, rng.as_deref_mut());
+            }
+            self.unplace(cell);
+            if *found >= limit || self.is_exhausted() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classic_search() {
+        let mut search = Search::new(&ClassicGrid, &[0; 81]).unwrap();
+        assert_eq!(search.run::<ThreadRng>(3, None), 3);
+        let solution = search.solution().unwrap();
+        for unit in CLASSIC_UNITS {
+            let mask = unit
+                .iter()
+                .fold(0u16, |mask, cell| mask | 1 << solution[*cell]);
+            assert_eq!(mask, 0b11_1111_1110);
+        }
+
+        let mut givens = [0; 81];
+        givens[0] = 5;
+        givens[8] = 5;
+        assert!(Search::new(&ClassicGrid, &givens).is_none());
+
+        let mut search = Search::new(&ClassicGrid, &[0; 81]).unwrap();
+        search.max_nodes = 10;
+        search.run::<ThreadRng>(usize::MAX, None);
+        assert!(search.is_exhausted());
+    }
+
+    #[test]
+    fn test_search_deadline() {
+        let mut search = Search::new(&ClassicGrid, &[0; 81]).unwrap();
+        search.deadline = Some(Instant::now());
+        search.run::<ThreadRng>(usize::MAX, None);
+        assert!(search.is_exhausted());
+        assert_eq!(search.check_deadline(), Err(DeadlineExceeded));
+        assert_eq!(search.nodes, 1);
+
+        let mut search = Search::new(&ClassicGrid, &[0; 81]).unwrap();
+        search.deadline = Some(Instant::now() + std::time::Duration::from_secs(60));
+        assert_eq!(search.run::<ThreadRng>(2000, None), 2000);
+        assert_eq!(search.check_deadline(), Ok(()));
+    }
+}
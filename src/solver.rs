@@ -1,5 +1,8 @@
 use crate::matrix::SudokuMatrix;
+use crate::search::{ClassicGrid, DeadlineExceeded, Search};
+use rand::prelude::*;
 use std::collections::HashSet;
+use std::time::Instant;
 
 /**
  * Sudoku solver internal state
@@ -107,9 +110,23 @@ fn solve_sudoku_derive_until_no_change(mat: &mut SudokuMatrix, state: &mut Sudok
 /**
  * Solve a partially-filled Sudoku puzzle by back-tracking.
  *
+ * The digits of a cell are tried in increasing order, so the search is deterministic.
+ *
  * Return true on success, false on failure.
  */
 pub fn solve_sudoku(mat: &mut SudokuMatrix, print_debug_info: bool) -> bool {
+    solve_sudoku_counting(mat, print_debug_info, &mut 0)
+}
+
+/**
+ * `solve_sudoku`, adding the number of search nodes visited to `nodes`.
+ */
+pub(crate) fn solve_sudoku_counting(
+    mat: &mut SudokuMatrix,
+    print_debug_info: bool,
+    nodes: &mut usize,
+) -> bool {
+    *nodes += 1;
     if print_debug_info {
         mat.print();
     }
@@ -141,13 +158,16 @@ pub fn solve_sudoku(mat: &mut SudokuMatrix, print_debug_info: bool) -> bool {
         }
     }
     if let Some((cr, cc)) = candidate {
-        for v in state.avail_vals[cr][cc].iter() {
+        // Sets iterate in a random order, which would change the search on every run.
+        let mut values: Vec<u8> = state.avail_vals[cr][cc].iter().copied().collect();
+        values.sort_unstable();
+        for v in values.iter() {
             if state.print_debug_info {
                 println!("Try set ({}, {}) to {}", cr, cc, v);
             }
             let mut new_mat = mat.clone();
             new_mat.set_value(cr, cc, *v);
-            if solve_sudoku(&mut new_mat, print_debug_info) {
+            if solve_sudoku_counting(&mut new_mat, print_debug_info, nodes) {
                 *mat = new_mat;
                 return true;
             } else if state.print_debug_info {
@@ -160,6 +180,162 @@ pub fn solve_sudoku(mat: &mut SudokuMatrix, print_debug_info: bool) -> bool {
     }
 }
 
+/**
+ * Cells of a matrix in row-major order, as searched by `Search` over a `ClassicGrid`.
+ */
+fn matrix_cells(mat: &SudokuMatrix) -> Vec<u8> {
+    (0..81)
+        .map(|cell| mat.get_value(cell / 9, cell % 9))
+        .collect()
+}
+
+fn cells_matrix(cells: &[u8]) -> SudokuMatrix {
+    let mut mat = SudokuMatrix::new();
+    for (cell, v) in cells.iter().enumerate() {
+        mat.set_value(cell / 9, cell % 9, *v);
+    }
+    mat
+}
+
+/**
+ * Count the solutions of a partially-filled Sudoku puzzle, stopping once `limit` solutions were found.
+ *
+ * A puzzle has a unique solution when `count_solutions(mat, 2) == 1`.
+ */
+pub fn count_solutions(mat: &SudokuMatrix, limit: usize) -> usize {
+    count_solutions_counting(mat, limit).0
+}
+
+/**
+ * Count the solutions like `count_solutions`, giving up once `deadline` has passed.
+ */
+pub fn count_solutions_before(
+    mat: &SudokuMatrix,
+    limit: usize,
+    deadline: Instant,
+) -> Result<usize, DeadlineExceeded> {
+    count_solutions_until(mat, limit, Some(deadline))
+}
+
+/**
+ * `count_solutions` with an optional deadline.
+ */
+pub(crate) fn count_solutions_until(
+    mat: &SudokuMatrix,
+    limit: usize,
+    deadline: Option<Instant>,
+) -> Result<usize, DeadlineExceeded> {
+    let Some(mut search) = Search::new(&ClassicGrid, &matrix_cells(mat)) else {
+        return Ok(0);
+    };
+    search.deadline = deadline;
+    let found = search.run::<ThreadRng>(limit, None);
+    search.check_deadline()?;
+    Ok(found)
+}
+
+/**
+ * `count_solutions`, also returning the number of search nodes visited.
+ */
+pub(crate) fn count_solutions_counting(mat: &SudokuMatrix, limit: usize) -> (usize, usize) {
+    let Some(mut search) = Search::new(&ClassicGrid, &matrix_cells(mat)) else {
+        return (0, 0);
+    };
+    let found = search.run::<ThreadRng>(limit, None);
+    (found, search.nodes)
+}
+
+/**
+ * Complete a partially-filled matrix with a random solution.
+ *
+ * Return `None` if there is no solution, or if none was found within `max_nodes` search nodes.
+ */
+pub(crate) fn fill_random_solution<R: Rng + ?Sized>(
+    mat: &SudokuMatrix,
+    rng: &mut R,
+    max_nodes: usize,
+) -> Option<SudokuMatrix> {
+    let mut search = Search::new(&ClassicGrid, &matrix_cells(mat))?;
+    search.max_nodes = max_nodes;
+    if search.run(1, Some(rng)) == 0 {
+        return None;
+    }
+    search.solution().map(cells_matrix)
+}
+
+/**
+ * Solve a puzzle with the bitmask search, which is much faster than `solve_sudoku` on
+ * hard puzzles.
+ *
+ * Return `None` if the puzzle has no solution. When it has several, the first one found
+ * is returned.
+ */
+pub fn find_solution(mat: &SudokuMatrix) -> Option<SudokuMatrix> {
+    find_solution_counting(mat).0
+}
+
+/**
+ * Solve a puzzle like `find_solution`, giving up once `deadline` has passed.
+ */
+pub fn find_solution_before(
+    mat: &SudokuMatrix,
+    deadline: Instant,
+) -> Result<Option<SudokuMatrix>, DeadlineExceeded> {
+    find_solution_until(mat, Some(deadline))
+}
+
+/**
+ * `find_solution` with an optional deadline.
+ */
+pub(crate) fn find_solution_until(
+    mat: &SudokuMatrix,
+    deadline: Option<Instant>,
+) -> Result<Option<SudokuMatrix>, DeadlineExceeded> {
+    let Some(mut search) = Search::new(&ClassicGrid, &matrix_cells(mat)) else {
+        return Ok(None);
+    };
+    search.deadline = deadline;
+    search.run::<ThreadRng>(1, None);
+    search.check_deadline()?;
+    Ok(search.solution().map(cells_matrix))
+}
+
+/**
+ * `find_solution`, also returning the number of search nodes visited.
+ */
+pub(crate) fn find_solution_counting(mat: &SudokuMatrix) -> (Option<SudokuMatrix>, usize) {
+    let Some(mut search) = Search::new(&ClassicGrid, &matrix_cells(mat)) else {
+        return (None, 0);
+    };
+    search.run::<ThreadRng>(1, None);
+    (search.solution().map(cells_matrix), search.nodes)
+}
+
+/**
+ * Determine whether a puzzle is minimal: it has a unique solution, and removing any
+ * given cell would break that uniqueness.
+ */
+pub fn is_minimal(mat: &SudokuMatrix) -> bool {
+    if count_solutions(mat, 2) != 1 {
+        return false;
+    }
+    let mut reduced = mat.clone();
+    for r in 0..9 {
+        for c in 0..9 {
+            let v = mat.get_value(r, c);
+            if v == 0 {
+                continue;
+            }
+            reduced.set_value(r, c, 0);
+            if count_solutions(&reduced, 2) == 1 {
+                return false;
+            }
+            reduced.set_value(r, c, v);
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,4 +365,69 @@ mod tests {
         // The state for that cell should now be empty (since it's filled)
         assert_eq!(state.avail_vals[0][8].len(), 0);
     }
+
+    #[test]
+    fn test_count_solutions() {
+        let mut mat = SudokuMatrix::new();
+        assert!(solve_sudoku(&mut mat, false));
+        assert_eq!(count_solutions(&mat, 2), 1);
+        let v = mat.get_value(4, 4);
+        mat.set_value(4, 4, 0);
+        assert_eq!(count_solutions(&mat, 2), 1);
+        mat.set_value(4, 4, v % 9 + 1);
+        assert_eq!(count_solutions(&mat, 2), 0);
+        assert_eq!(count_solutions(&SudokuMatrix::new(), 5), 5);
+    }
+
+    #[test]
+    fn test_search_before_deadline() {
+        let empty = SudokuMatrix::new();
+        let far = Instant::now() + std::time::Duration::from_secs(60);
+        assert_eq!(count_solutions_before(&empty, 5, far), Ok(5));
+        assert!(find_solution_before(&empty, far).unwrap().is_some());
+        let passed = Instant::now();
+        assert_eq!(
+            count_solutions_before(&empty, usize::MAX, passed),
+            Err(DeadlineExceeded)
+        );
+        assert_eq!(find_solution_before(&empty, passed), Err(DeadlineExceeded));
+    }
+
+    #[test]
+    fn test_solve_sudoku_is_deterministic() {
+        let puzzle: SudokuMatrix =
+            "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......"
+                .parse()
+                .unwrap();
+        let solve = |mat: &SudokuMatrix| {
+            let mut solved = mat.clone();
+            let mut nodes = 0;
+            assert!(solve_sudoku_counting(&mut solved, false, &mut nodes));
+            (solved, nodes)
+        };
+        let first = solve(&puzzle);
+        assert!(first.1 > 1);
+        assert_eq!(solve(&puzzle), first);
+        // Digits are tried in increasing order.
+        let (solved, _) = solve(&SudokuMatrix::new());
+        assert_eq!(&solved.to_line()[..9], "123456789");
+    }
+
+    #[test]
+    fn test_find_solution() {
+        let mut mat = SudokuMatrix::new();
+        let solution = find_solution(&mat).unwrap();
+        assert!(solution.is_complete() && solution.is_compatible());
+        mat.set_value(0, 0, 1);
+        mat.set_value(0, 1, 1);
+        assert!(find_solution(&mat).is_none());
+    }
+
+    #[test]
+    fn test_is_minimal() {
+        let mut mat = SudokuMatrix::new();
+        assert!(solve_sudoku(&mut mat, false));
+        assert!(!is_minimal(&mat));
+        assert!(!is_minimal(&SudokuMatrix::new()));
+    }
 }
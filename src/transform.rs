@@ -0,0 +1,454 @@
+use crate::matrix::SudokuMatrix;
+use rand::prelude::*;
+
+const IDENTITY: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+const REVERSED: [usize; 9] = [8, 7, 6, 5, 4, 3, 2, 1, 0];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/**
+ * A validity-preserving transformation of a Sudoku grid.
+ *
+ * Any composition of rotations, mirrors, transposes, digit relabelings, band and stack
+ * swaps, and row and column swaps inside a band is represented by a single value. The
+ * same transform can be applied to a puzzle and to its solution, so the transformed
+ * solution stays the solution of the transformed puzzle.
+ */
+pub struct SudokuTransform {
+    /// Whether the source grid is transposed before the permutations are applied.
+    transpose: bool,
+    /// Row `r` of the result is taken from row `row_perm[r]` of the (transposed) source.
+    row_perm: [usize; 9],
+    /// Column `c` of the result is taken from column `col_perm[c]` of the (transposed) source.
+    col_perm: [usize; 9],
+    /// Digit `v` of the source becomes `digit_map[v]`. `digit_map[0]` is always `0`.
+    digit_map: [u8; 10],
+}
+
+impl Default for SudokuTransform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl SudokuTransform {
+    /**
+     * The transform that leaves every grid unchanged.
+     */
+    pub fn identity() -> Self {
+        SudokuTransform {
+            transpose: false,
+            row_perm: IDENTITY,
+            col_perm: IDENTITY,
+            digit_map: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        }
+    }
+
+    /**
+     * Rotate the grid by 90 degrees clockwise.
+     */
+    pub fn rotate_cw() -> Self {
+        SudokuTransform {
+            transpose: true,
+            col_perm: REVERSED,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Rotate the grid by 90 degrees counter-clockwise.
+     */
+    pub fn rotate_ccw() -> Self {
+        SudokuTransform {
+            transpose: true,
+            row_perm: REVERSED,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Rotate the grid by 180 degrees.
+     */
+    pub fn rotate_180() -> Self {
+        SudokuTransform {
+            row_perm: REVERSED,
+            col_perm: REVERSED,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Mirror the grid left to right.
+     */
+    pub fn mirror_horizontal() -> Self {
+        SudokuTransform {
+            col_perm: REVERSED,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Mirror the grid top to bottom.
+     */
+    pub fn mirror_vertical() -> Self {
+        SudokuTransform {
+            row_perm: REVERSED,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Transpose the grid along its main diagonal.
+     */
+    pub fn transpose() -> Self {
+        SudokuTransform {
+            transpose: true,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Relabel the digits.
+     *
+     * # Arguments
+     * * `mapping` - `mapping[v - 1]` is the new digit for digit `v`.
+     *
+     * # Panics
+     * If `mapping` is not a permutation of the digits 1 to 9.
+     */
+    pub fn relabel(mapping: &[u8; 9]) -> Self {
+        let mut sorted = *mapping;
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            [1, 2, 3, 4, 5, 6, 7, 8, 9],
+            "digit mapping must be a permutation of 1 to 9"
+        );
+        let mut digit_map = [0u8; 10];
+        digit_map[1..].copy_from_slice(mapping);
+        SudokuTransform {
+            digit_map,
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Swap two bands (groups of three rows).
+     *
+     * # Panics
+     * If a band index is not in `0..3`.
+     */
+    pub fn swap_bands(a: usize, b: usize) -> Self {
+        SudokuTransform {
+            row_perm: swap_groups(a, b),
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Swap two stacks (groups of three columns).
+     *
+     * # Panics
+     * If a stack index is not in `0..3`.
+     */
+    pub fn swap_stacks(a: usize, b: usize) -> Self {
+        SudokuTransform {
+            col_perm: swap_groups(a, b),
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Swap two rows of the same band.
+     *
+     * # Panics
+     * If the rows are out of range or belong to different bands.
+     */
+    pub fn swap_rows(a: usize, b: usize) -> Self {
+        SudokuTransform {
+            row_perm: swap_lines(a, b),
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Swap two columns of the same stack.
+     *
+     * # Panics
+     * If the columns are out of range or belong to different stacks.
+     */
+    pub fn swap_cols(a: usize, b: usize) -> Self {
+        SudokuTransform {
+            col_perm: swap_lines(a, b),
+            ..Self::identity()
+        }
+    }
+
+    /**
+     * Draw a random transform among the roughly 3.3 million grid layouts and 9! digit relabelings.
+     */
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        digits.shuffle(rng);
+        SudokuTransform {
+            transpose: rng.random(),
+            row_perm: random_line_permutation(rng),
+            col_perm: random_line_permutation(rng),
+            ..Self::relabel(&digits)
+        }
+    }
+
+    /**
+     * Compose two transforms: the result applies `self` first, then `next`.
+     */
+    pub fn then(&self, next: &SudokuTransform) -> SudokuTransform {
+        let digit_map = std::array::from_fn(|v| next.digit_map[self.digit_map[v] as usize]);
+        if next.transpose {
+            SudokuTransform {
+                transpose: !self.transpose,
+                row_perm: std::array::from_fn(|r| self.col_perm[next.row_perm[r]]),
+                col_perm: std::array::from_fn(|c| self.row_perm[next.col_perm[c]]),
+                digit_map,
+            }
+        } else {
+            SudokuTransform {
+                transpose: self.transpose,
+                row_perm: std::array::from_fn(|r| self.row_perm[next.row_perm[r]]),
+                col_perm: std::array::from_fn(|c| self.col_perm[next.col_perm[c]]),
+                digit_map,
+            }
+        }
+    }
+
+    /**
+     * Return the transform that undoes this one.
+     */
+    pub fn inverse(&self) -> SudokuTransform {
+        let mut row_perm = [0; 9];
+        let mut col_perm = [0; 9];
+        let mut digit_map = [0u8; 10];
+        for idx in 0..9 {
+            row_perm[self.row_perm[idx]] = idx;
+            col_perm[self.col_perm[idx]] = idx;
+        }
+        for v in 0..10 {
+            digit_map[self.digit_map[v] as usize] = v as u8;
+        }
+        if self.transpose {
+            SudokuTransform {
+                transpose: true,
+                row_perm: col_perm,
+                col_perm: row_perm,
+                digit_map,
+            }
+        } else {
+            SudokuTransform {
+                transpose: false,
+                row_perm,
+                col_perm,
+                digit_map,
+            }
+        }
+    }
+
+    /**
+     * Apply the transform to a matrix and return the transformed copy.
+     */
+    pub fn apply(&self, mat: &SudokuMatrix) -> SudokuMatrix {
+        let mut result = SudokuMatrix::new();
+        for r in 0..9 {
+            for c in 0..9 {
+                let (sr, sc) = (self.row_perm[r], self.col_perm[c]);
+                let v = if self.transpose {
+                    mat.get_value(sc, sr)
+                } else {
+                    mat.get_value(sr, sc)
+                };
+                result.set_value(r, c, self.digit_map[v as usize]);
+            }
+        }
+        result
+    }
+}
+
+fn swap_groups(a: usize, b: usize) -> [usize; 9] {
+    assert!(a < 3 && b < 3, "band and stack indices must be in 0..3");
+    let mut perm = IDENTITY;
+    for idx in 0..3 {
+        perm.swap(a * 3 + idx, b * 3 + idx);
+    }
+    perm
+}
+
+fn swap_lines(a: usize, b: usize) -> [usize; 9] {
+    assert!(a < 9 && b < 9, "row and column indices must be in 0..9");
+    assert_eq!(
+        a / 3,
+        b / 3,
+        "only lines of the same band or stack can be swapped"
+    );
+    let mut perm = IDENTITY;
+    perm.swap(a, b);
+    perm
+}
+
+fn random_line_permutation<R: Rng>(rng: &mut R) -> [usize; 9] {
+    let mut groups = [0, 1, 2];
+    groups.shuffle(rng);
+    let mut perm = [0; 9];
+    for (g, group) in groups.iter().enumerate() {
+        let mut lines = [0, 1, 2];
+        lines.shuffle(rng);
+        for (idx, line) in lines.iter().enumerate() {
+            perm[g * 3 + idx] = group * 3 + line;
+        }
+    }
+    perm
+}
+
+impl SudokuMatrix {
+    /**
+     * Return a copy rotated by 90 degrees clockwise.
+     */
+    pub fn rotate_cw(&self) -> SudokuMatrix {
+        SudokuTransform::rotate_cw().apply(self)
+    }
+
+    /**
+     * Return a copy rotated by 90 degrees counter-clockwise.
+     */
+    pub fn rotate_ccw(&self) -> SudokuMatrix {
+        SudokuTransform::rotate_ccw().apply(self)
+    }
+
+    /**
+     * Return a copy rotated by 180 degrees.
+     */
+    pub fn rotate_180(&self) -> SudokuMatrix {
+        SudokuTransform::rotate_180().apply(self)
+    }
+
+    /**
+     * Return a copy mirrored left to right.
+     */
+    pub fn mirror_horizontal(&self) -> SudokuMatrix {
+        SudokuTransform::mirror_horizontal().apply(self)
+    }
+
+    /**
+     * Return a copy mirrored top to bottom.
+     */
+    pub fn mirror_vertical(&self) -> SudokuMatrix {
+        SudokuTransform::mirror_vertical().apply(self)
+    }
+
+    /**
+     * Return a transposed copy.
+     */
+    pub fn transpose(&self) -> SudokuMatrix {
+        SudokuTransform::transpose().apply(self)
+    }
+
+    /**
+     * Return a copy with relabeled digits. See `SudokuTransform::relabel`.
+     */
+    pub fn relabel_digits(&self, mapping: &[u8; 9]) -> SudokuMatrix {
+        SudokuTransform::relabel(mapping).apply(self)
+    }
+
+    /**
+     * Return a copy with two bands swapped. See `SudokuTransform::swap_bands`.
+     */
+    pub fn swap_bands(&self, a: usize, b: usize) -> SudokuMatrix {
+        SudokuTransform::swap_bands(a, b).apply(self)
+    }
+
+    /**
+     * Return a copy with two stacks swapped. See `SudokuTransform::swap_stacks`.
+     */
+    pub fn swap_stacks(&self, a: usize, b: usize) -> SudokuMatrix {
+        SudokuTransform::swap_stacks(a, b).apply(self)
+    }
+
+    /**
+     * Return a copy with two rows of a band swapped. See `SudokuTransform::swap_rows`.
+     */
+    pub fn swap_rows(&self, a: usize, b: usize) -> SudokuMatrix {
+        SudokuTransform::swap_rows(a, b).apply(self)
+    }
+
+    /**
+     * Return a copy with two columns of a stack swapped. See `SudokuTransform::swap_cols`.
+     */
+    pub fn swap_cols(&self, a: usize, b: usize) -> SudokuMatrix {
+        SudokuTransform::swap_cols(a, b).apply(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::solve_sudoku;
+
+    fn solved_matrix() -> SudokuMatrix {
+        let mut mat = SudokuMatrix::new();
+        assert!(solve_sudoku(&mut mat, false));
+        mat
+    }
+
+    #[test]
+    fn test_rotations() {
+        let mut mat = SudokuMatrix::new();
+        mat.set_value(0, 1, 5);
+        assert_eq!(mat.rotate_cw().get_value(1, 8), 5);
+        assert_eq!(mat.rotate_ccw().get_value(7, 0), 5);
+        assert_eq!(mat.rotate_180().get_value(8, 7), 5);
+        assert_eq!(mat.rotate_cw().rotate_cw(), mat.rotate_180());
+        assert_eq!(mat.rotate_cw().rotate_ccw(), mat);
+    }
+
+    #[test]
+    fn test_mirrors_and_swaps() {
+        let mut mat = SudokuMatrix::new();
+        mat.set_value(1, 2, 7);
+        assert_eq!(mat.mirror_horizontal().get_value(1, 6), 7);
+        assert_eq!(mat.mirror_vertical().get_value(7, 2), 7);
+        assert_eq!(mat.transpose().get_value(2, 1), 7);
+        assert_eq!(mat.swap_bands(0, 2).get_value(7, 2), 7);
+        assert_eq!(mat.swap_stacks(0, 1).get_value(1, 5), 7);
+        assert_eq!(mat.swap_rows(1, 2).get_value(2, 2), 7);
+        assert_eq!(mat.swap_cols(2, 0).get_value(1, 0), 7);
+        assert_eq!(
+            mat.relabel_digits(&[9, 8, 7, 6, 5, 4, 3, 2, 1])
+                .get_value(1, 2),
+            3
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_swap_rows_across_bands() {
+        SudokuTransform::swap_rows(2, 3);
+    }
+
+    #[test]
+    fn test_composition_and_inverse() {
+        let mut rng = rand::rng();
+        let mat = solved_matrix();
+        for _ in 0..20 {
+            let a = SudokuTransform::random(&mut rng);
+            let b = SudokuTransform::random(&mut rng);
+            assert_eq!(a.then(&b).apply(&mat), b.apply(&a.apply(&mat)));
+            assert_eq!(a.inverse().apply(&a.apply(&mat)), mat);
+            assert_eq!(a.then(&a.inverse()), SudokuTransform::identity());
+        }
+    }
+
+    #[test]
+    fn test_transform_keeps_solution() {
+        let solution = solved_matrix();
+        let transform = SudokuTransform::random(&mut rand::rng());
+        let transformed = transform.apply(&solution);
+        assert!(transformed.is_complete());
+        assert!(transformed.is_compatible());
+    }
+}
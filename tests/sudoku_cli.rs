@@ -0,0 +1,179 @@
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+const UNIQUE: &str =
+    ".9.....54.34.5.....8...19......2....51...8.7.729..5.4..5.7.4..8.......1...83.....";
+
+/**
+ * Run the `sudoku` binary with puzzles on standard input.
+ */
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sudoku"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn json_lines(output: &Output) -> Vec<Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_solve_exit_codes() {
+    let empty = ".".repeat(81);
+    let conflict = format!("11{}", ".".repeat(79));
+
+    let output = run(&["solve"], UNIQUE);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains('.'));
+
+    // Several solutions: the first one is still printed.
+    let output = run(&["solve"], &empty);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim().len(), 81);
+
+    let output = run(&["solve"], &conflict);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run(&["solve", "/nonexistent/puzzles.txt"], "");
+    assert_eq!(output.status.code(), Some(2));
+
+    // A malformed line is reported in its place and the next puzzles are still solved.
+    let output = run(&["solve"], &format!("{}\n1234\n{}\n", UNIQUE, UNIQUE));
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(
+        lines[1].starts_with("# puzzle 2: <stdin>: line 2:"),
+        "{}",
+        lines[1]
+    );
+    assert_eq!(lines[0], lines[2]);
+}
+
+#[test]
+fn test_other_exit_codes() {
+    let empty = ".".repeat(81);
+    assert_eq!(run(&["count"], &empty).status.code(), Some(0));
+    assert_eq!(run(&["validate"], UNIQUE).status.code(), Some(0));
+    assert_eq!(run(&["validate"], &empty).status.code(), Some(1));
+    assert_eq!(run(&["rate"], &empty).status.code(), Some(1));
+    assert_eq!(run(&["frobnicate"], "").status.code(), Some(2));
+}
+
+#[test]
+fn test_generate_difficulty_fallback() {
+    let args = [
+        "generate",
+        "-d",
+        "medium",
+        "--symmetry",
+        "rotational90",
+        "--json",
+    ];
+    let output = run(&[&args[..], &["--seed", "1"]].concat(), "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(json_lines(&output)[0]["difficulty"], "medium");
+
+    // No medium puzzle is found with this seed, so an easy one is written with a warning.
+    let output = run(&[&args[..], &["--seed", "3"]].concat(), "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(json_lines(&output)[0]["difficulty"], "easy");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("easy instead of medium"));
+}
+
+#[test]
+fn test_json_output() {
+    let input = format!("{}\n{}\n", UNIQUE, ".".repeat(81));
+    let output = run(&["solve", "--json", "-j", "2"], &input);
+    let lines = json_lines(&output);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["index"], 1);
+    assert_eq!(lines[0]["solved"], true);
+    assert_eq!(lines[0]["unique"], true);
+    assert_eq!(lines[0]["solution"].as_str().unwrap().len(), 81);
+    assert_eq!(lines[1]["index"], 2);
+    assert_eq!(lines[1]["unique"], false);
+    // Fields keep their order, with the index first.
+    let keys: Vec<&String> = lines[0].as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["index", "solved", "solution", "unique"]);
+
+    let output = run(&["count", "--json", "--limit", "3"], &".".repeat(81));
+    assert_eq!(
+        json_lines(&output),
+        [serde_json::json!({"index": 1, "solutions": 3, "limit_reached": true})]
+    );
+
+    let output = run(
+        &["rate", "--json", "--from", "line"],
+        &format!("1234\n{}\n", UNIQUE),
+    );
+    let lines = json_lines(&output);
+    assert_eq!(lines[0]["index"], 1);
+    assert!(lines[0]["error"].as_str().unwrap().contains("line 1"));
+    assert_eq!(lines[1]["index"], 2);
+    assert!(lines[1]["difficulty"].is_string());
+
+    let output = run(&["validate", "--json"], &format!("11{}", ".".repeat(79)));
+    assert_eq!(json_lines(&output)[0]["problem"], "conflicting clues");
+
+    // Benchmark results use the same encoder.
+    let output = run(
+        &["bench", "--json", "--backend", "bitmask", "--set", "easy"],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    let lines = json_lines(&output);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["set"], "easy");
+    assert_eq!(lines[0]["solved"], lines[0]["runs"]);
+}
+
+#[test]
+fn test_batch_timeout() {
+    let path = std::env::temp_dir().join(format!("sudoku-cli-batch-{}.txt", std::process::id()));
+    let start = Instant::now();
+    let output = run(
+        &[
+            "batch",
+            "-n",
+            "1000",
+            "-d",
+            "hard",
+            "--symmetry",
+            "rotational90",
+            "-j",
+            "2",
+            "--format",
+            "line",
+            "--timeout",
+            "0.1",
+            "-q",
+            "-o",
+            path.to_str().unwrap(),
+        ],
+        "",
+    );
+    let elapsed = start.elapsed();
+    let _ = std::fs::remove_file(&path);
+    // Such puzzles take around a second each in debug builds, but the workers stop at the
+    // deadline instead of finishing them.
+    assert_eq!(output.status.code(), Some(1));
+    assert!(elapsed < Duration::from_millis(600), "took {:?}", elapsed);
+}